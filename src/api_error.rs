@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Error body returned by the Danfoss API
+///
+/// The token endpoint answers with OAuth errors such as
+/// `{"error": "invalid_client", "error_description": "..."}`, the gateway in
+/// front of the API with `{"fault": {"faultstring": "...", "detail":
+/// {"errorcode": "..."}}}`. Fields that are not present stay `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    /// OAuth error code, e.g. `invalid_client`
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Human readable description of an OAuth error
+    #[serde(default)]
+    pub error_description: Option<String>,
+    /// Generic error message
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Gateway fault, e.g. a rate limit or quota violation
+    #[serde(default)]
+    pub fault: Option<ApiFault>,
+}
+
+/// Fault reported by the API gateway
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiFault {
+    /// Human readable description
+    #[serde(default)]
+    pub faultstring: Option<String>,
+    /// Machine readable details
+    #[serde(default)]
+    pub detail: Option<ApiFaultDetail>,
+}
+
+/// Machine readable details of an [`ApiFault`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiFaultDetail {
+    /// Error code, e.g. `policies.ratelimit.QuotaViolation`
+    #[serde(default)]
+    pub errorcode: Option<String>,
+}
+
+impl ApiErrorBody {
+    /// Machine readable error code, if any
+    pub fn code(&self) -> Option<&str> {
+        self.error.as_deref().or_else(|| {
+            self.fault
+                .as_ref()
+                .and_then(|fault| fault.detail.as_ref())
+                .and_then(|detail| detail.errorcode.as_deref())
+        })
+    }
+
+    /// Human readable description, if any
+    pub fn description(&self) -> Option<&str> {
+        self.error_description
+            .as_deref()
+            .or(self.message.as_deref())
+            .or_else(|| {
+                self.fault
+                    .as_ref()
+                    .and_then(|fault| fault.faultstring.as_deref())
+            })
+    }
+}
+
+/// Error returned when the API answers with an unsuccessful HTTP status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// API path that was requested
+    pub path: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Parsed error body, `None` if the body was not a known error format
+    pub body: Option<ApiErrorBody>,
+    /// Response body as received
+    pub raw_body: String,
+    /// How long to wait before retrying, from the `Retry-After` header
+    pub retry_after: Option<Duration>,
+}
+
+impl ApiError {
+    pub(crate) fn new(path: &str, status: u16, body: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            status,
+            body: serde_json::from_str::<ApiErrorBody>(body)
+                .ok()
+                .filter(|body| body != &ApiErrorBody::default()),
+            raw_body: body.to_string(),
+            retry_after: None,
+        }
+    }
+
+    pub(crate) fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Whether the credentials or the access token were rejected (401)
+    pub fn is_unauthorized(&self) -> bool {
+        self.status == 401
+    }
+
+    /// Whether the API failed to handle the request (5xx)
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.status)
+    }
+
+    /// Whether the request was throttled (429) without using up the quota
+    pub fn is_throttled(&self) -> bool {
+        self.status == 429 && !self.is_quota_exhausted()
+    }
+
+    /// Whether the quota of the API plan is used up
+    pub fn is_quota_exhausted(&self) -> bool {
+        self.body
+            .as_ref()
+            .and_then(ApiErrorBody::code)
+            .is_some_and(|code| code.to_lowercase().contains("quota"))
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} returned HTTP {}", self.path, self.status)?;
+        let body = self.body.as_ref();
+        if let Some(code) = body.and_then(ApiErrorBody::code) {
+            write!(f, " ({})", code)?;
+        }
+        if let Some(description) = body.and_then(ApiErrorBody::description) {
+            write!(f, ": {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ApiError {}
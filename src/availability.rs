@@ -0,0 +1,248 @@
+use crate::runtime::SystemTime;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Number of recent intervals the polling cadence is estimated from
+const CADENCE_WINDOW: usize = 9;
+
+/// Gaps kept per device; older ones are dropped
+pub const MAX_OBSERVATION_GAPS: usize = 100;
+
+/// Online/offline history of a single device
+///
+/// The struct is serializable so it can be persisted between runs and put
+/// back into [`AllyApi::availability`](crate::AllyApi::availability).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceAvailability {
+    /// When the device was first observed
+    pub first_seen: SystemTime,
+    /// When the device was last observed in a devices response
+    pub last_seen: SystemTime,
+    /// When the device was last observed online
+    pub last_online: Option<SystemTime>,
+    /// Accumulated time the device was observed online
+    pub online_time: Duration,
+    /// Accumulated time the device was observed offline
+    pub offline_time: Duration,
+    /// Online status at the last observation
+    pub online: bool,
+    /// Periods in which the device was not observed at all, e.g. because the
+    /// polling process was down. They count neither as online nor offline.
+    /// Adjacent gaps are merged and at most [`MAX_OBSERVATION_GAPS`] are kept.
+    #[serde(default)]
+    pub gaps: Vec<ObservationGap>,
+    /// Most recent intervals between observations, oldest first
+    #[serde(default)]
+    intervals: Vec<Duration>,
+}
+
+/// A period without observations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObservationGap {
+    /// Last observation before the gap
+    pub start: SystemTime,
+    /// First observation after the gap
+    pub end: SystemTime,
+}
+
+impl ObservationGap {
+    /// Length of the gap
+    pub fn duration(&self) -> Duration {
+        self.end.duration_since(self.start).unwrap_or_default()
+    }
+}
+
+impl DeviceAvailability {
+    /// Start tracking a device observed at `now`
+    pub fn new(online: bool, now: SystemTime) -> Self {
+        Self {
+            first_seen: now,
+            last_seen: now,
+            last_online: online.then_some(now),
+            online_time: Duration::ZERO,
+            offline_time: Duration::ZERO,
+            online,
+            gaps: vec![],
+            intervals: vec![],
+        }
+    }
+
+    /// Record an observation. The time since the previous observation is
+    /// attributed to the state seen back then, unless it is a gap: longer
+    /// than both `max_gap` and three times the usual interval between
+    /// observations, so a slower polling cadence than expected is learned.
+    pub fn observe(&mut self, online: bool, now: SystemTime, max_gap: Duration) {
+        let elapsed = now.duration_since(self.last_seen).unwrap_or_default();
+        let max_gap = self
+            .typical_interval()
+            .map_or(max_gap, |interval| max_gap.max(interval * 3));
+        if elapsed > max_gap {
+            self.record_gap(ObservationGap {
+                start: self.last_seen,
+                end: now,
+            });
+        } else if self.online {
+            self.online_time += elapsed;
+        } else {
+            self.offline_time += elapsed;
+        }
+        if self.intervals.len() == CADENCE_WINDOW {
+            self.intervals.remove(0);
+        }
+        self.intervals.push(elapsed);
+        self.last_seen = now;
+        self.online = online;
+        if online {
+            self.last_online = Some(now);
+        }
+    }
+
+    /// Median of the recent intervals between observations, `None` before
+    /// the second observation
+    pub fn typical_interval(&self) -> Option<Duration> {
+        let mut intervals = self.intervals.clone();
+        intervals.sort();
+        intervals.get(intervals.len() / 2).copied()
+    }
+
+    fn record_gap(&mut self, gap: ObservationGap) {
+        match self.gaps.last_mut() {
+            Some(last) if last.end == gap.start => last.end = gap.end,
+            _ => self.gaps.push(gap),
+        }
+        if self.gaps.len() > MAX_OBSERVATION_GAPS {
+            self.gaps.remove(0);
+        }
+    }
+
+    /// Gaps overlapping the period from `start` to `end`
+    pub fn gaps_between(&self, start: SystemTime, end: SystemTime) -> Vec<ObservationGap> {
+        self.gaps
+            .iter()
+            .filter(|gap| gap.end > start && gap.start < end)
+            .copied()
+            .collect()
+    }
+
+    /// Share of the tracked time the device was online, in percent.
+    /// Gaps are left out. `None` until some time has been tracked.
+    pub fn availability_percentage(&self) -> Option<f64> {
+        let total = self.online_time + self.offline_time;
+        if total.is_zero() {
+            return None;
+        }
+        Some(self.online_time.as_secs_f64() / total.as_secs_f64() * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_GAP: Duration = Duration::from_secs(300);
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn time_is_attributed_to_the_previous_state() {
+        let mut availability = DeviceAvailability::new(true, at(0));
+        availability.observe(false, at(60), MAX_GAP);
+        availability.observe(false, at(120), MAX_GAP);
+        availability.observe(true, at(180), MAX_GAP);
+
+        assert_eq!(availability.online_time, Duration::from_secs(60));
+        assert_eq!(availability.offline_time, Duration::from_secs(120));
+        assert_eq!(availability.last_online, Some(at(180)));
+        assert_eq!(
+            availability.availability_percentage(),
+            Some(60.0 / 180.0 * 100.0)
+        );
+        assert!(availability.gaps.is_empty());
+    }
+
+    #[test]
+    fn long_silence_is_a_gap() {
+        let mut availability = DeviceAvailability::new(true, at(0));
+        availability.observe(true, at(60), MAX_GAP);
+        availability.observe(true, at(3660), MAX_GAP);
+
+        assert_eq!(availability.online_time, Duration::from_secs(60));
+        assert_eq!(
+            availability.gaps,
+            vec![ObservationGap {
+                start: at(60),
+                end: at(3660)
+            }]
+        );
+        assert_eq!(availability.gaps[0].duration(), Duration::from_secs(3600));
+        assert_eq!(availability.gaps_between(at(0), at(100)).len(), 1);
+        assert!(availability.gaps_between(at(3660), at(4000)).is_empty());
+    }
+
+    #[test]
+    fn typical_interval_is_the_median_of_recent_intervals() {
+        let mut availability = DeviceAvailability::new(true, at(0));
+        assert_eq!(availability.typical_interval(), None);
+        for secs in [10, 20, 1000, 1030] {
+            availability.observe(true, at(secs), Duration::from_secs(5000));
+        }
+        // Intervals 10, 10, 980, 30
+        assert_eq!(
+            availability.typical_interval(),
+            Some(Duration::from_secs(30))
+        );
+
+        let mut availability = DeviceAvailability::new(true, at(0));
+        for i in 1..=20 {
+            availability.observe(true, at(i * 60), MAX_GAP);
+        }
+        assert_eq!(availability.intervals.len(), CADENCE_WINDOW);
+    }
+
+    #[test]
+    fn slower_cadence_than_max_gap_is_learned() {
+        let mut availability = DeviceAvailability::new(true, at(0));
+        // The first interval exceeds `MAX_GAP` before any cadence is known
+        availability.observe(true, at(600), MAX_GAP);
+        availability.observe(true, at(1200), Duration::from_secs(1000));
+        availability.observe(true, at(1800), MAX_GAP);
+        availability.observe(true, at(3000), MAX_GAP);
+
+        assert_eq!(availability.gaps.len(), 1);
+        assert_eq!(availability.online_time, Duration::from_secs(2400));
+        // Beyond three times the usual interval
+        availability.observe(true, at(5000), MAX_GAP);
+        assert_eq!(availability.gaps.len(), 2);
+    }
+
+    #[test]
+    fn adjacent_gaps_are_merged_and_old_ones_dropped() {
+        let mut availability = DeviceAvailability::new(true, at(0));
+        availability.record_gap(ObservationGap {
+            start: at(0),
+            end: at(10),
+        });
+        availability.record_gap(ObservationGap {
+            start: at(10),
+            end: at(20),
+        });
+        assert_eq!(
+            availability.gaps,
+            vec![ObservationGap {
+                start: at(0),
+                end: at(20)
+            }]
+        );
+
+        for i in 0..MAX_OBSERVATION_GAPS as u64 + 5 {
+            availability.record_gap(ObservationGap {
+                start: at(100 + i * 10),
+                end: at(105 + i * 10),
+            });
+        }
+        assert_eq!(availability.gaps.len(), MAX_OBSERVATION_GAPS);
+        assert_eq!(availability.gaps[0].start, at(150));
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{bool_value, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Warning banner state of a device (`banner_ctrl`), the alert the Danfoss
+/// Ally app shows for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BannerState {
+    /// No warning is shown
+    Hidden,
+    /// A warning is shown
+    Shown,
+    /// A state not known to this crate, as reported
+    Other(String),
+}
+
+impl Device {
+    /// Warning banner state, if reported
+    pub fn banner(&self) -> Option<BannerState> {
+        let value = self.status_value(StatusCode::BannerCtrl)?;
+        Some(match bool_value(value) {
+            Some(true) => BannerState::Shown,
+            Some(false) => BannerState::Hidden,
+            None => match value {
+                Value::String(state) => BannerState::Other(state.clone()),
+                value => BannerState::Other(value.to_string()),
+            },
+        })
+    }
+
+    /// Whether the device currently shows a warning banner
+    pub fn has_warning(&self) -> bool {
+        matches!(
+            self.banner(),
+            Some(BannerState::Shown | BannerState::Other(_))
+        )
+    }
+}
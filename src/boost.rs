@@ -0,0 +1,133 @@
+use crate::runtime::{self, SystemTime};
+use crate::{AllyApi, AllyError, StatusCode, TemperatureUnit, Unsupported};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A temporarily raised setpoint, see [`AllyApi::boost`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Boost {
+    /// Id of the boosted device
+    pub device_id: String,
+    /// Setpoint in degrees Celsius to restore once the boost ends
+    pub previous: f32,
+    /// Boosted setpoint in degrees Celsius
+    pub target: f32,
+    /// When the previous setpoint is restored
+    pub until: SystemTime,
+}
+
+impl AllyApi {
+    /// Raise the setpoint of a thermostat to `target_celsius` for `duration`.
+    ///
+    /// A background task restores the previous setpoint when the boost ends,
+    /// through a clone of the client. If that fails, the first
+    /// [`AllyApi::get_devices`] after the boost ended or an explicit
+    /// [`AllyApi::revert_expired_boosts`] tries again. Boosting an already
+    /// boosted device extends the boost and keeps the original setpoint.
+    pub async fn boost(
+        &self,
+        device_id: &str,
+        target_celsius: f32,
+        duration: Duration,
+    ) -> Result<(), AllyError> {
+        let _setpoint = self.lock_setpoint(device_id).await;
+        let previous = match self.boost_of(device_id) {
+            Some(boost) => boost.previous,
+            None => self.current_setpoint(device_id).await?,
+        };
+        self.set_temperature(device_id, target_celsius).await?;
+        let boost = Boost {
+            device_id: device_id.to_string(),
+            previous,
+            target: target_celsius,
+            until: SystemTime::now() + duration,
+        };
+        {
+            let boosts = &mut self.shared().boosts;
+            boosts.retain(|boost| boost.device_id != device_id);
+            boosts.push(boost.clone());
+        }
+        self.schedule_revert(boost, duration);
+        Ok(())
+    }
+
+    /// Revert `boost` after `duration` in a background task, unless it was
+    /// cancelled or replaced by then. Without a tokio runtime the boost is
+    /// left to [`AllyApi::revert_expired_boosts`].
+    pub(crate) fn schedule_revert(&self, boost: Boost, duration: Duration) {
+        let api = self.clone();
+        runtime::spawn(async move {
+            runtime::sleep(duration).await;
+            api.revert_boost(&boost).await;
+        });
+    }
+
+    /// Boosts that have not been reverted yet
+    pub fn boosts(&self) -> Vec<Boost> {
+        self.shared().boosts.clone()
+    }
+
+    /// The boost of a device, if it has not been reverted yet
+    pub fn boost_of(&self, device_id: &str) -> Option<Boost> {
+        self.shared()
+            .boosts
+            .iter()
+            .find(|boost| boost.device_id == device_id)
+            .cloned()
+    }
+
+    /// End the boost of a device right away and restore its previous setpoint
+    pub async fn cancel_boost(&self, device_id: &str) -> Result<(), AllyError> {
+        let _setpoint = self.lock_setpoint(device_id).await;
+        let Some(boost) = self.boost_of(device_id) else {
+            return Ok(());
+        };
+        self.set_temperature(device_id, boost.previous).await?;
+        self.shared().boosts.retain(|active| active != &boost);
+        Ok(())
+    }
+
+    /// Restore the previous setpoint of every boost that has ended.
+    ///
+    /// Boosts that fail to revert are kept and retried on the next call.
+    pub async fn revert_expired_boosts(&self) {
+        let now = SystemTime::now();
+        let expired: Vec<Boost> = self
+            .boosts()
+            .into_iter()
+            .filter(|boost| boost.until <= now)
+            .collect();
+        for boost in expired {
+            self.revert_boost(&boost).await;
+        }
+    }
+
+    /// Restore the previous setpoint of a boost and forget the boost, unless
+    /// it was cancelled or replaced meanwhile. On failure it is kept for the
+    /// next [`AllyApi::revert_expired_boosts`].
+    async fn revert_boost(&self, boost: &Boost) {
+        let _setpoint = self.lock_setpoint(&boost.device_id).await;
+        if self.boost_of(&boost.device_id).as_ref() != Some(boost) {
+            return;
+        }
+        match self.set_temperature(&boost.device_id, boost.previous).await {
+            Ok(()) => self.shared().boosts.retain(|active| active != boost),
+            Err(e) => warn!("Could not end boost of {}. {}", boost.device_id, e),
+        }
+    }
+
+    /// Setpoint of a device in degrees Celsius as reported by the API right
+    /// now. The device list may be older than the last change of the setpoint.
+    pub(crate) async fn current_setpoint(&self, device_id: &str) -> Result<f32, AllyError> {
+        self.get_device(device_id)
+            .await?
+            .setpoint(TemperatureUnit::Celsius)
+            .ok_or_else(|| {
+                Unsupported {
+                    device_id: device_id.to_string(),
+                    code: StatusCode::TempSet,
+                }
+                .into()
+            })
+    }
+}
@@ -0,0 +1,214 @@
+use crate::config::{base_url_from_env, base_url_setting, credential_from_env};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::NetworkConfig;
+use crate::{AllyApi, ConfigError, HttpTransport, RateLimit, ReqwestTransport, RetryPolicy};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures an [`AllyApi`] before it is created, see [`AllyApi::builder`]
+///
+/// Settings that are not given keep the defaults of [`AllyApi::with_credentials`].
+/// Without credentials, they are read from `DANFOSS_API_KEY` and
+/// `DANFOSS_API_SECRET` like [`AllyApi::try_new`] does. Without a base URL,
+/// `DANFOSS_BASE_URL` is used if set.
+///
+/// On WebAssembly the browser manages connections, so timeouts and network
+/// settings are not available there.
+///
+/// ```no_run
+/// use danfoss_ally_rs::{AllyApi, RateLimit, RetryPolicy};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let api = AllyApi::builder()
+///     .credentials("key", "secret")
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("my-thermostat-dashboard/1.0")
+///     .polling_interval(Duration::from_secs(60))
+///     .retry(RetryPolicy::default())
+///     .rate_limit(RateLimit::per_minute(100))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AllyApiBuilder {
+    credentials: Option<(String, String)>,
+    base_url: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    network: Option<NetworkConfig>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    polling_interval: Option<Duration>,
+    token_refresh_margin: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    rate_limit: Option<Option<RateLimit>>,
+    token_rate_limit: Option<Option<RateLimit>>,
+}
+
+impl AllyApiBuilder {
+    /// Start with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// API key and secret of the account
+    pub fn credentials(
+        mut self,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((api_key.into(), api_secret.into()));
+        self
+    }
+
+    /// Base URL of the API, e.g. of a gateway in front of it or of a mock
+    /// server in tests. Takes precedence over `DANFOSS_BASE_URL`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Timeout of each request, from connecting until the body is read
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for establishing a connection
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// `User-Agent` header sent with every request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Name resolution and IP family settings, see [`NetworkConfig`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn network(mut self, network: NetworkConfig) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Send requests through an existing client, e.g. one with custom TLS
+    /// roots or a proxy. The client keeps its own settings: timeouts, user
+    /// agent and network settings given to the builder are ignored.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.transport = Some(Arc::new(ReqwestTransport::new(client)));
+        self
+    }
+
+    /// Send requests through a custom [`HttpTransport`]. Like with
+    /// [`AllyApiBuilder::client`], timeouts, user agent and network settings
+    /// given to the builder are ignored.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// See [`AllyApi::polling_interval`]
+    pub fn polling_interval(mut self, interval: Duration) -> Self {
+        self.polling_interval = Some(interval);
+        self
+    }
+
+    /// See [`AllyApi::token_refresh_margin`]
+    pub fn token_refresh_margin(mut self, margin: Duration) -> Self {
+        self.token_refresh_margin = Some(margin);
+        self
+    }
+
+    /// Retry requests that failed for transient reasons, see [`AllyApi::retry`]
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Limit for API requests, `None` to disable it
+    pub fn rate_limit(mut self, limit: impl Into<Option<RateLimit>>) -> Self {
+        self.rate_limit = Some(limit.into());
+        self
+    }
+
+    /// Limit for token requests, `None` to disable it
+    pub fn token_rate_limit(mut self, limit: impl Into<Option<RateLimit>>) -> Self {
+        self.token_rate_limit = Some(limit.into());
+        self
+    }
+
+    /// Create the client
+    pub fn build(self) -> Result<AllyApi, ConfigError> {
+        let (api_key, api_secret) = match self.credentials {
+            Some(credentials) => credentials,
+            None => (
+                credential_from_env("DANFOSS_API_KEY")?,
+                credential_from_env("DANFOSS_API_SECRET")?,
+            ),
+        };
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let mut client = reqwest::Client::builder();
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if let Some(timeout) = self.timeout {
+                        client = client.timeout(timeout);
+                    }
+                    if let Some(timeout) = self.connect_timeout {
+                        client = client.connect_timeout(timeout);
+                    }
+                    if let Some(network) = self.network {
+                        client = network.apply(client);
+                    }
+                }
+                if let Some(user_agent) = self.user_agent {
+                    client = client.user_agent(user_agent);
+                }
+                let client = client.build().map_err(ConfigError::HttpClient)?;
+                Arc::new(ReqwestTransport::new(client))
+            }
+        };
+        let mut api = AllyApi::with_transport(transport, api_key, api_secret);
+        match self.base_url {
+            Some(base_url) => api.base_url = base_url_setting("base_url", &base_url)?,
+            None => {
+                if let Some(base_url) = base_url_from_env()? {
+                    api.base_url = base_url;
+                }
+            }
+        }
+        if let Some(interval) = self.polling_interval {
+            api.polling_interval = interval;
+        }
+        if let Some(margin) = self.token_refresh_margin {
+            api.token_refresh_margin = margin;
+        }
+        if let Some(policy) = self.retry {
+            api.retry = Some(policy);
+        }
+        if let Some(limit) = self.rate_limit {
+            api.rate_limit = limit;
+        }
+        if let Some(limit) = self.token_rate_limit {
+            api.token_rate_limit = limit;
+        }
+        Ok(api)
+    }
+}
+
+impl AllyApi {
+    /// Configure a new client, see [`AllyApiBuilder`]
+    pub fn builder() -> AllyApiBuilder {
+        AllyApiBuilder::new()
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{Device, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// Features of a device, derived from the status codes it reports
+///
+/// This lets generic code adapt to mixed installations of thermostats,
+/// room sensors and controllers without hard-coding device types.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Reports a measured temperature
+    pub temperature: bool,
+    /// Has an adjustable target temperature
+    pub setpoint: bool,
+    /// Reports relative humidity
+    pub humidity: bool,
+    /// Runs on batteries and reports the level
+    pub battery: bool,
+    /// Supports switching between operating modes
+    pub mode: bool,
+    /// Has a child lock
+    pub child_lock: bool,
+    /// Detects open windows
+    pub window_detection: bool,
+    /// Accepts temperatures measured by an external room sensor
+    pub external_sensor: bool,
+    /// Supports load balancing between radiators in a room
+    pub load_balancing: bool,
+    /// Can be switched on and off
+    pub switch: bool,
+}
+
+impl Device {
+    /// Features of this device, derived from the status codes it reports
+    pub fn capabilities(&self) -> Capabilities {
+        let has = |codes: &[StatusCode]| codes.iter().any(|code| self.status_value(code).is_some());
+        Capabilities {
+            temperature: has(&[StatusCode::VaTemperature, StatusCode::TempCurrent]),
+            setpoint: has(&[StatusCode::TempSet]),
+            humidity: has(&[StatusCode::VaHumidity]),
+            battery: has(&[StatusCode::BatteryPercentage]),
+            mode: has(&[StatusCode::Mode]),
+            child_lock: has(&[StatusCode::ChildLock]),
+            window_detection: has(&[StatusCode::WindowState, StatusCode::WindowToggle]),
+            external_sensor: has(&[StatusCode::ExtMeasuredRs]),
+            load_balancing: has(&[StatusCode::LoadBalanceEnable]),
+            switch: has(&[StatusCode::Switch]),
+        }
+    }
+}
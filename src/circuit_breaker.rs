@@ -0,0 +1,238 @@
+use crate::runtime::Instant;
+use crate::{AllyApi, AllyError};
+use std::time::Duration;
+
+/// Suggested wait while the trial request of a half-open circuit is pending
+const PROBE_RETRY: Duration = Duration::from_secs(1);
+
+/// State of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally
+    Closed,
+    /// Requests fail fast until the cool-down ends
+    Open {
+        /// When the cool-down ends
+        until: Instant,
+    },
+    /// The cool-down has ended and a single trial request decides whether
+    /// the circuit closes again. Other requests fail fast meanwhile.
+    HalfOpen,
+}
+
+/// Stops sending requests after repeated upstream failures, see
+/// [`AllyApi::set_circuit_breaker`]
+///
+/// Only failures that point at the Danfoss cloud count, see
+/// [`AllyError::is_transient`]. After `failure_threshold` of them in a row,
+/// requests fail with [`AllyError::CircuitOpen`] for `cool_down`. Then one
+/// request is let through, by any clone of the client; if it fails, the
+/// circuit opens again. A trial request that never reports back, e.g. because
+/// its future was dropped, is given up after `cool_down`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long requests fail fast once the circuit is open
+    pub cool_down: Duration,
+    failures: u32,
+    state: CircuitState,
+    /// When the trial request of the half-open state was let through
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Open after `failure_threshold` consecutive failures for `cool_down`
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cool_down,
+            failures: 0,
+            state: CircuitState::Closed,
+            probe_started: None,
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// Consecutive failures counted so far
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Close the circuit and forget past failures
+    pub fn reset(&mut self) {
+        self.failures = 0;
+        self.state = CircuitState::Closed;
+        self.probe_started = None;
+    }
+
+    /// Whether a request may be sent now. An expired cool-down moves the
+    /// circuit to half-open and lets the calling request through as the
+    /// trial; others wait until it is recorded.
+    fn allow(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open { until } if now < until => Err(until - now),
+            CircuitState::Open { .. } => {
+                self.state = CircuitState::HalfOpen;
+                self.probe_started = Some(now);
+                Ok(())
+            }
+            CircuitState::HalfOpen => match self.probe_started {
+                Some(started) if now < started + self.cool_down => {
+                    Err(PROBE_RETRY.min(started + self.cool_down - now))
+                }
+                _ => {
+                    self.probe_started = Some(now);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn record(&mut self, failed: bool, now: Instant) {
+        if !failed {
+            if self.state != CircuitState::Closed {
+                info!("Requests succeed again, closing the circuit");
+            }
+            self.reset();
+            return;
+        }
+        self.failures = self.failures.saturating_add(1);
+        if self.state == CircuitState::HalfOpen || self.failures >= self.failure_threshold {
+            warn!(
+                "{} consecutive failures, pausing requests for {:?}",
+                self.failures, self.cool_down
+            );
+            self.state = CircuitState::Open {
+                until: now + self.cool_down,
+            };
+            self.probe_started = None;
+        }
+    }
+}
+
+impl AllyApi {
+    /// Fail fast after repeated upstream failures, see [`CircuitBreaker`].
+    /// Clones of the client share the circuit breaker. Default: None
+    pub fn set_circuit_breaker(&self, breaker: Option<CircuitBreaker>) {
+        self.shared().circuit_breaker = breaker;
+    }
+
+    /// Copy of the circuit breaker, `None` if none is configured
+    pub fn circuit_breaker(&self) -> Option<CircuitBreaker> {
+        self.shared().circuit_breaker.clone()
+    }
+
+    /// State of the circuit breaker, `None` if none is configured
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.shared()
+            .circuit_breaker
+            .as_ref()
+            .map(CircuitBreaker::state)
+    }
+
+    /// Fail fast with [`AllyError::CircuitOpen`] while the circuit is open
+    pub(crate) fn check_circuit(&self) -> Result<(), AllyError> {
+        match &mut self.shared().circuit_breaker {
+            Some(breaker) => breaker
+                .allow(Instant::now())
+                .map_err(|retry_in| AllyError::CircuitOpen { retry_in }),
+            None => Ok(()),
+        }
+    }
+
+    /// Count the outcome of a request towards the circuit breaker
+    pub(crate) fn record_outcome<T>(&self, result: &Result<T, AllyError>) {
+        if let Some(breaker) = &mut self.shared().circuit_breaker {
+            let failed = matches!(result, Err(e) if e.is_transient());
+            breaker.record(failed, Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOL_DOWN: Duration = Duration::from_secs(10);
+
+    fn opened(now: Instant) -> CircuitBreaker {
+        let mut breaker = CircuitBreaker::new(2, COOL_DOWN);
+        breaker.record(true, now);
+        breaker.record(true, now);
+        breaker
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, COOL_DOWN);
+        breaker.record(true, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record(true, now);
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Open {
+                until: now + COOL_DOWN
+            }
+        );
+        assert_eq!(
+            breaker.allow(now + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, COOL_DOWN);
+        breaker.record(true, now);
+        breaker.record(false, now);
+        breaker.record(true, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.failures(), 1);
+    }
+
+    #[test]
+    fn half_open_lets_a_single_trial_through() {
+        let now = Instant::now();
+        let mut breaker = opened(now);
+        let later = now + COOL_DOWN;
+        assert_eq!(breaker.allow(later), Ok(()));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow(later).is_err());
+        breaker.record(false, later);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.allow(later), Ok(()));
+    }
+
+    #[test]
+    fn failed_trial_opens_the_circuit_again() {
+        let now = Instant::now();
+        let mut breaker = opened(now);
+        let later = now + COOL_DOWN;
+        assert_eq!(breaker.allow(later), Ok(()));
+        breaker.record(true, later);
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Open {
+                until: later + COOL_DOWN
+            }
+        );
+    }
+
+    #[test]
+    fn lost_trial_is_replaced_after_the_cool_down() {
+        let now = Instant::now();
+        let mut breaker = opened(now);
+        let trial = now + COOL_DOWN;
+        assert_eq!(breaker.allow(trial), Ok(()));
+        assert!(breaker.allow(trial + COOL_DOWN / 2).is_err());
+        assert_eq!(breaker.allow(trial + COOL_DOWN), Ok(()));
+    }
+}
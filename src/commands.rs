@@ -0,0 +1,210 @@
+use crate::protocol::{self, CommandsResponse};
+use crate::{AllyApi, AllyError, InvalidSetpoint, StatusCode, TemperatureUnit};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when the API accepted a command request but reported
+/// that the command was not applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRejected {
+    /// Id of the device the command was sent to
+    pub device_id: String,
+}
+
+impl fmt::Display for CommandRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Command for device {} was rejected", self.device_id)
+    }
+}
+
+impl Error for CommandRejected {}
+
+/// Per-device results of [`AllyApi::send_commands_batch`], keyed by device id
+pub type BatchResults = Vec<(String, Result<(), AllyError>)>;
+
+/// A single device command, e.g. `{"code": "child_lock", "value": true}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    /// Status code to set
+    pub code: StatusCode,
+    /// New value in the wire format of the code
+    pub value: Value,
+}
+
+impl Command {
+    /// Create a command from a code and any serializable value
+    pub fn new(code: impl Into<StatusCode>, value: impl Into<Value>) -> Self {
+        Self {
+            code: code.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Error returned before sending a command to a cached device that does
+/// not report the command's status code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsupported {
+    /// Id of the device
+    pub device_id: String,
+    /// Status code of the command
+    pub code: StatusCode,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Device {} does not support {}",
+            self.device_id, self.code
+        )
+    }
+}
+
+impl Error for Unsupported {}
+
+/// Error returned before sending a command whose value lies outside the
+/// range the device accepts
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutOfRange {
+    /// Status code of the command
+    pub code: StatusCode,
+    /// Requested value
+    pub value: f32,
+    /// Smallest accepted value
+    pub min: f32,
+    /// Largest accepted value
+    pub max: f32,
+}
+
+impl OutOfRange {
+    /// Check that `value` lies within `min..=max`
+    pub(crate) fn check(code: StatusCode, value: f32, min: f32, max: f32) -> Result<(), Self> {
+        if (min..=max).contains(&value) {
+            return Ok(());
+        }
+        Err(Self {
+            code,
+            value,
+            min,
+            max,
+        })
+    }
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Value {} for {} is outside of {}..={}",
+            self.value, self.code, self.min, self.max
+        )
+    }
+}
+
+impl Error for OutOfRange {}
+
+impl AllyApi {
+    /// Send commands to a device in a single request.
+    ///
+    /// Values are passed through unchanged, so temperatures have to be given
+    /// in tenths of a degree Celsius.
+    pub async fn send_commands(
+        &self,
+        device_id: &str,
+        commands: Vec<Command>,
+    ) -> Result<(), AllyError> {
+        let correlation_id = self.start_operation();
+        self.post_commands(device_id, &commands, &correlation_id)
+            .await
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    /// Send commands to several devices concurrently.
+    ///
+    /// At most [`AllyApi::max_concurrent_requests`] requests are in flight at
+    /// once. Every request goes through the same quota, rate limit, circuit
+    /// breaker, retry and token handling as [`AllyApi::send_commands`].
+    /// Results are returned per device in the order of `batch`. The outer
+    /// error is only returned if no access token could be fetched.
+    pub async fn send_commands_batch(
+        &self,
+        batch: Vec<(String, Vec<Command>)>,
+    ) -> Result<BatchResults, AllyError> {
+        let correlation_id = self.start_operation();
+        self.refresh_token_if_needed(&correlation_id)
+            .await
+            .map_err(|e| AllyError::correlated(&correlation_id, e))?;
+        let correlation_id = &correlation_id;
+        let results = stream::iter(batch)
+            .map(|(device_id, commands)| async move {
+                let result = self
+                    .post_commands(&device_id, &commands, correlation_id)
+                    .await
+                    .map_err(|e| AllyError::correlated(correlation_id, e));
+                (device_id, result)
+            })
+            .buffered(self.max_concurrent_requests.max(1))
+            .collect()
+            .await;
+        Ok(results)
+    }
+
+    /// Set the target temperature of a thermostat in degrees Celsius.
+    ///
+    /// Fails with [`InvalidSetpoint`](crate::InvalidSetpoint) without sending
+    /// a request if the device would not accept the setpoint, see
+    /// [`AllyApi::setpoint_limits`].
+    pub async fn set_temperature(&self, device_id: &str, celsius: f32) -> Result<(), AllyError> {
+        let command = self.setpoint_command(device_id, celsius)?;
+        self.send_commands(device_id, vec![command]).await
+    }
+
+    /// Set the target temperature of a thermostat in `unit`, e.g. in
+    /// [`AllyApi::temperature_unit`].
+    ///
+    /// Other units than Celsius are converted and rounded to the nearest
+    /// setpoint step of the device, so 70 °F becomes 21 °C. The result is
+    /// checked like in [`AllyApi::set_temperature`].
+    pub async fn set_temperature_in(
+        &self,
+        device_id: &str,
+        value: f32,
+        unit: TemperatureUnit,
+    ) -> Result<(), AllyError> {
+        let celsius = match unit {
+            TemperatureUnit::Celsius => value,
+            unit => self
+                .setpoint_limits_for(device_id)
+                .round(unit.celsius_of(value)),
+        };
+        self.set_temperature(device_id, celsius).await
+    }
+
+    /// Command setting the setpoint of a device to `celsius`, if the device
+    /// accepts it
+    pub(crate) fn setpoint_command(
+        &self,
+        device_id: &str,
+        celsius: f32,
+    ) -> Result<Command, InvalidSetpoint> {
+        self.validate_setpoint(device_id, celsius)?;
+        let value = TemperatureUnit::Celsius.encode_deci_celsius(celsius);
+        Ok(Command::new(StatusCode::TempSet, value))
+    }
+
+    async fn post_commands(
+        &self,
+        device_id: &str,
+        commands: &[Command],
+        correlation_id: &str,
+    ) -> Result<(), AllyError> {
+        let path = protocol::device_commands_path(device_id)?;
+        let body = protocol::commands_body(commands);
+        let response: CommandsResponse = self.post_json(&path, &body, correlation_id).await?;
+        response.into_result(device_id)
+    }
+}
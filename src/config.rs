@@ -0,0 +1,367 @@
+use crate::AllyApi;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Error returned when the client configuration is incomplete or cannot be read
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A required environment variable is not set or not valid unicode
+    MissingVariable(String),
+    /// A required setting is neither in the configuration file nor in the
+    /// environment variable that overrides it
+    MissingSetting {
+        /// Key in the configuration file
+        key: String,
+        /// Environment variable overriding the key
+        variable: String,
+    },
+    /// A setting has a value that cannot be used
+    InvalidSetting {
+        /// Key in the configuration file or environment variable
+        key: String,
+        /// Why the value cannot be used
+        reason: String,
+    },
+    /// The configuration file cannot be read
+    Read {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Underlying error
+        error: io::Error,
+    },
+    /// The configuration file is not valid TOML or has unexpected values
+    Parse {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Underlying error
+        error: toml::de::Error,
+    },
+    /// The HTTP client cannot be created
+    HttpClient(reqwest::Error),
+    /// The platform secret store cannot be accessed
+    #[cfg(feature = "keyring")]
+    Keyring(keyring::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingVariable(name) => {
+                write!(
+                    f,
+                    "{} is not set. Please set the environment variable.",
+                    name
+                )
+            }
+            ConfigError::MissingSetting { key, variable } => write!(
+                f,
+                "{} is missing. Set it in the configuration file or in {}.",
+                key, variable
+            ),
+            ConfigError::InvalidSetting { key, reason } => {
+                write!(f, "Invalid value for {}: {}", key, reason)
+            }
+            ConfigError::Read { path, error } => {
+                write!(f, "Cannot read {}: {}", path.display(), error)
+            }
+            ConfigError::Parse { path, error } => {
+                write!(f, "Cannot parse {}: {}", path.display(), error)
+            }
+            ConfigError::HttpClient(error) => {
+                write!(f, "Cannot create the HTTP client: {}", error)
+            }
+            #[cfg(feature = "keyring")]
+            ConfigError::Keyring(error) => write!(f, "Cannot access the keyring: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { error, .. } => Some(error),
+            ConfigError::Parse { error, .. } => Some(error),
+            ConfigError::HttpClient(error) => Some(error),
+            #[cfg(feature = "keyring")]
+            ConfigError::Keyring(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Contents of a configuration file, see [`AllyApi::from_config`]
+///
+/// ```toml
+/// api_key = "..."
+/// api_secret = "..."
+/// polling_interval = 60
+/// base_url = "https://api.danfoss.com"
+/// token_refresh_margin = 60
+///
+/// [logging]
+/// level = "info"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// API key, overridden by `DANFOSS_API_KEY`
+    pub api_key: Option<String>,
+    /// API secret, overridden by `DANFOSS_API_SECRET`
+    pub api_secret: Option<String>,
+    /// Polling interval in seconds, overridden by `DANFOSS_POLLING_INTERVAL`
+    pub polling_interval: Option<u64>,
+    /// Base URL of the API, overridden by `DANFOSS_BASE_URL`
+    pub base_url: Option<String>,
+    /// Seconds before expiry the token is renewed, overridden by
+    /// `DANFOSS_TOKEN_REFRESH_MARGIN`
+    pub token_refresh_margin: Option<u64>,
+    /// Logging options
+    pub logging: LoggingConfig,
+}
+
+/// Logging section of a [`Config`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Log filter in `env_logger` syntax, e.g. `info` or
+    /// `danfoss_ally_rs=debug`, overridden by `RUST_LOG`. Default: None,
+    /// the client does not set up logging. Only applied with the
+    /// `env_logger` feature
+    pub level: Option<String>,
+    /// Include timestamps in log lines. Default: true
+    pub timestamps: Option<bool>,
+}
+
+impl Config {
+    /// Read a configuration file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|error| ConfigError::Read {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        toml::from_str(&content).map_err(|error| ConfigError::Parse {
+            path: path.to_path_buf(),
+            error,
+        })
+    }
+
+    /// Replace settings with the values of their environment variables,
+    /// where set
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+        if let Some(api_key) = var("DANFOSS_API_KEY") {
+            self.api_key = Some(api_key);
+        }
+        if let Some(api_secret) = var("DANFOSS_API_SECRET") {
+            self.api_secret = Some(api_secret);
+        }
+        if let Some(interval) = var("DANFOSS_POLLING_INTERVAL") {
+            self.polling_interval = Some(seconds("DANFOSS_POLLING_INTERVAL", &interval)?);
+        }
+        if let Some(margin) = var("DANFOSS_TOKEN_REFRESH_MARGIN") {
+            self.token_refresh_margin = Some(seconds("DANFOSS_TOKEN_REFRESH_MARGIN", &margin)?);
+        }
+        if let Some(base_url) = var("DANFOSS_BASE_URL") {
+            self.base_url = Some(base_url);
+        }
+        if let Some(level) = var("RUST_LOG") {
+            self.logging.level = Some(level);
+        }
+        Ok(())
+    }
+
+    /// Set up `env_logger` with the logging options. Does nothing if no
+    /// level is configured or a logger is already installed.
+    #[cfg(feature = "env_logger")]
+    pub fn init_logging(&self) {
+        if let Some(level) = &self.logging.level {
+            let mut builder = env_logger::Builder::new();
+            builder.parse_filters(level);
+            if self.logging.timestamps == Some(false) {
+                builder.format_timestamp(None);
+            }
+            let _ = builder.try_init();
+        }
+    }
+}
+
+impl AllyApi {
+    /// Create new danfoss ally client from a TOML configuration file, see
+    /// [`Config`]. Environment variables override the values in the file,
+    /// and logging is set up if a level is configured.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let mut config = Config::load(path)?;
+        config.apply_env()?;
+        Self::from_config_values(config)
+    }
+
+    /// Create new danfoss ally client from already loaded configuration
+    /// values. Environment variables are not consulted.
+    pub fn from_config_values(config: Config) -> Result<Self, ConfigError> {
+        #[cfg(feature = "env_logger")]
+        config.init_logging();
+        let missing = |key: &str, variable: &str| ConfigError::MissingSetting {
+            key: key.to_string(),
+            variable: variable.to_string(),
+        };
+        let api_key = config
+            .api_key
+            .ok_or_else(|| missing("api_key", "DANFOSS_API_KEY"))?;
+        let api_secret = config
+            .api_secret
+            .ok_or_else(|| missing("api_secret", "DANFOSS_API_SECRET"))?;
+        let mut api = Self::with_credentials(api_key, api_secret);
+        if let Some(seconds) = config.polling_interval {
+            api.polling_interval = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = config.token_refresh_margin {
+            api.token_refresh_margin = Duration::from_secs(seconds);
+        }
+        if let Some(base_url) = config.base_url {
+            api.base_url = base_url_setting("base_url", &base_url)?;
+        }
+        Ok(api)
+    }
+}
+
+/// Parse a number of seconds from an environment variable
+fn seconds(name: &str, value: &str) -> Result<u64, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::InvalidSetting {
+            key: name.to_string(),
+            reason: format!("{:?} is not a number of seconds", value),
+        })
+}
+
+/// Check that a base URL is an http or https URL and drop trailing slashes
+pub(crate) fn base_url_setting(key: &str, base_url: &str) -> Result<String, ConfigError> {
+    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+        return Err(ConfigError::InvalidSetting {
+            key: key.to_string(),
+            reason: format!("{:?} is not an http or https URL", base_url),
+        });
+    }
+    Ok(base_url.trim_end_matches('/').to_string())
+}
+
+/// Read the base URL from `DANFOSS_BASE_URL`, `None` if it is not set
+pub(crate) fn base_url_from_env() -> Result<Option<String>, ConfigError> {
+    env::var("DANFOSS_BASE_URL")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| base_url_setting("DANFOSS_BASE_URL", &value))
+        .transpose()
+}
+
+/// Read a credential from an environment variable. Empty values count as
+/// not set. With the `keyring` feature, credentials that are not set are
+/// looked up in the platform secret store.
+pub(crate) fn credential_from_env(name: &str) -> Result<String, ConfigError> {
+    let value = env::var(name).ok().filter(|value| !value.trim().is_empty());
+    #[cfg(feature = "keyring")]
+    if value.is_none() {
+        return crate::secret_store::credential_from_keyring(name);
+    }
+    value.ok_or_else(|| ConfigError::MissingVariable(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+        api_key = "file-key"
+        api_secret = "file-secret"
+        polling_interval = 120
+        token_refresh_margin = 90
+        base_url = "https://ally.example/"
+
+        [logging]
+        level = "debug"
+        timestamps = false
+    "#;
+
+    #[test]
+    fn file_settings_are_read() {
+        let config: Config = toml::from_str(FILE).unwrap();
+        assert_eq!(config.api_key.as_deref(), Some("file-key"));
+        assert_eq!(config.polling_interval, Some(120));
+        assert_eq!(config.logging.level.as_deref(), Some("debug"));
+        assert_eq!(config.logging.timestamps, Some(false));
+        assert_eq!(toml::from_str::<Config>("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn unreadable_files_are_reported_with_their_path() {
+        let path = env::temp_dir().join(format!("danfoss-ally-config-{}.toml", std::process::id()));
+        assert!(matches!(Config::load(&path), Err(ConfigError::Read { .. })));
+
+        fs::write(&path, "polling_interval = \"soon\"").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        match error {
+            ConfigError::Parse { path: reported, .. } => assert_eq!(reported, path),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        // The only test changing these variables, so tests running in
+        // parallel do not interfere
+        let mut config: Config = toml::from_str(FILE).unwrap();
+        env::set_var("DANFOSS_API_KEY", "env-key");
+        env::set_var("DANFOSS_POLLING_INTERVAL", " 30 ");
+        env::set_var("DANFOSS_TOKEN_REFRESH_MARGIN", " ");
+        let applied = config.apply_env();
+        env::set_var("DANFOSS_POLLING_INTERVAL", "soon");
+        let invalid = config.clone().apply_env();
+        for name in [
+            "DANFOSS_API_KEY",
+            "DANFOSS_POLLING_INTERVAL",
+            "DANFOSS_TOKEN_REFRESH_MARGIN",
+        ] {
+            env::remove_var(name);
+        }
+
+        applied.unwrap();
+        assert_eq!(config.api_key.as_deref(), Some("env-key"));
+        assert_eq!(config.api_secret.as_deref(), Some("file-secret"));
+        assert_eq!(config.polling_interval, Some(30));
+        // Empty variables count as not set
+        assert_eq!(config.token_refresh_margin, Some(90));
+        assert!(matches!(
+            invalid,
+            Err(ConfigError::InvalidSetting { key, .. }) if key == "DANFOSS_POLLING_INTERVAL"
+        ));
+    }
+
+    #[test]
+    fn config_values_configure_the_client() {
+        let mut config: Config = toml::from_str(FILE).unwrap();
+        config.logging.level = None;
+        let api = AllyApi::from_config_values(config.clone()).unwrap();
+        assert_eq!(api.polling_interval, Duration::from_secs(120));
+        assert_eq!(api.token_refresh_margin, Duration::from_secs(90));
+        assert_eq!(api.base_url, "https://ally.example");
+
+        config.base_url = Some("ally.example".to_string());
+        assert!(matches!(
+            AllyApi::from_config_values(config.clone()),
+            Err(ConfigError::InvalidSetting { .. })
+        ));
+        config.api_secret = None;
+        assert!(matches!(
+            AllyApi::from_config_values(config),
+            Err(ConfigError::MissingSetting { key, .. }) if key == "api_secret"
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    bool_value, numeric_value, AllyApi, AllyError, Command, Device, DeviceKind, OutOfRange,
+    StatusCode, Unsupported,
+};
+
+/// Largest temperature offset in degrees Celsius, in either direction
+const MAX_OFFSET: f32 = 2.5;
+
+impl Device {
+    /// Whether the child lock is engaged (`child_lock`), if reported
+    pub fn child_lock(&self) -> Option<bool> {
+        self.status_value(StatusCode::ChildLock)
+            .and_then(bool_value)
+    }
+
+    /// Whether the radiator is marked as covered (`radiator_covered`), in
+    /// which case regulation relies on an external room sensor
+    pub fn radiator_covered(&self) -> Option<bool> {
+        self.status_value(StatusCode::RadiatorCovered)
+            .and_then(bool_value)
+    }
+
+    /// Whether a switchable device such as the Ally Electric Heat Plug is
+    /// on (`switch`), if reported
+    pub fn switch_state(&self) -> Option<bool> {
+        self.status_value(StatusCode::Switch).and_then(bool_value)
+    }
+
+    /// Calibration offset added to the measured temperature in degrees
+    /// Celsius, if reported
+    pub fn temperature_offset(&self) -> Option<f32> {
+        self.status_value(StatusCode::TempOffset)
+            .and_then(numeric_value)
+            .map(|offset| offset / 10.0)
+    }
+}
+
+impl AllyApi {
+    /// Engage or release the child lock of a thermostat
+    pub async fn set_child_lock(&self, device_id: &str, locked: bool) -> Result<(), AllyError> {
+        self.send_commands(device_id, vec![Command::new(StatusCode::ChildLock, locked)])
+            .await
+    }
+
+    /// Correct the measured temperature of a thermostat by `offset_celsius`,
+    /// which must lie within ±2.5 °C
+    pub async fn set_temperature_offset(
+        &self,
+        device_id: &str,
+        offset_celsius: f32,
+    ) -> Result<(), AllyError> {
+        OutOfRange::check(
+            StatusCode::TempOffset,
+            offset_celsius,
+            -MAX_OFFSET,
+            MAX_OFFSET,
+        )?;
+        let value = (offset_celsius * 10.0).round() as i32;
+        self.send_commands(device_id, vec![Command::new(StatusCode::TempOffset, value)])
+            .await
+    }
+
+    /// Mark a radiator as covered or uncovered
+    pub async fn set_radiator_covered(
+        &self,
+        device_id: &str,
+        covered: bool,
+    ) -> Result<(), AllyError> {
+        self.send_commands(
+            device_id,
+            vec![Command::new(StatusCode::RadiatorCovered, covered)],
+        )
+        .await
+    }
+
+    /// Switch a device such as the Ally Electric Heat Plug on or off.
+    ///
+    /// Fails with [`Unsupported`] without sending a request if the device is
+    /// cached, is no electric heat plug and does not report a switch state.
+    pub async fn set_switch(&self, device_id: &str, on: bool) -> Result<(), AllyError> {
+        let cached = self.devices.iter().find(|device| device.id == device_id);
+        let switchable = |device: &Device| {
+            device.kind() == DeviceKind::ElectricHeatPlug || device.capabilities().switch
+        };
+        if cached.is_some_and(|device| !switchable(device)) {
+            return Err(Unsupported {
+                device_id: device_id.to_string(),
+                code: StatusCode::Switch,
+            }
+            .into());
+        }
+        self.send_commands(device_id, vec![Command::new(StatusCode::Switch, on)])
+            .await
+    }
+}
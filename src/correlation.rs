@@ -0,0 +1,15 @@
+use crate::runtime::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Create an id for one logical operation, unique within the process and
+/// unlikely to repeat across restarts
+pub(crate) fn new_correlation_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let sequence = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:08x}-{:04x}", millis as u32, sequence & 0xffff)
+}
@@ -0,0 +1,154 @@
+use crate::{AllyApi, Device};
+use serde::{Deserialize, Serialize};
+
+/// Kind of an Ally device, derived from its free-form `device_type`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    /// Danfoss Ally radiator thermostat
+    RadiatorThermostat,
+    /// Danfoss Ally room sensor
+    RoomSensor,
+    /// Danfoss Icon controller or room thermostat for floor heating
+    IconController,
+    /// Danfoss Ally gateway
+    Gateway,
+    /// Danfoss Ally electric heat plug
+    ElectricHeatPlug,
+    /// A device type not known to this crate, as reported
+    Unknown(String),
+}
+
+impl DeviceKind {
+    /// Classify a `device_type` as reported by the API
+    ///
+    /// ```
+    /// use danfoss_ally_rs::DeviceKind;
+    ///
+    /// assert_eq!(
+    ///     DeviceKind::from_device_type("Danfoss Ally™ Radiator Thermostat"),
+    ///     DeviceKind::RadiatorThermostat
+    /// );
+    /// assert_eq!(DeviceKind::from_device_type("Danfoss Ally™ Gateway"), DeviceKind::Gateway);
+    /// ```
+    pub fn from_device_type(device_type: &str) -> Self {
+        let lower = device_type.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        NAMES
+            .iter()
+            .find(|(name, _)| words.windows(name.len()).any(|window| window == *name))
+            .map(|(_, kind)| kind)
+            .or_else(|| {
+                MODELS
+                    .iter()
+                    .find(|(prefix, _)| words.iter().any(|word| is_model(word, prefix)))
+                    .map(|(_, kind)| kind)
+            })
+            .cloned()
+            .unwrap_or_else(|| DeviceKind::Unknown(device_type.to_string()))
+    }
+}
+
+/// Names in `device_type`, matched as whole words, and the kind they stand
+/// for. Names are checked before [`MODELS`], so an "Icon Room Sensor" is a
+/// room sensor.
+const NAMES: &[(&[&str], DeviceKind)] = &[
+    (&["gateway"], DeviceKind::Gateway),
+    (&["room", "sensor"], DeviceKind::RoomSensor),
+    (&["plug"], DeviceKind::ElectricHeatPlug),
+    (&["radiator", "thermostat"], DeviceKind::RadiatorThermostat),
+];
+
+/// Model designations and the kind they stand for
+const MODELS: &[(&str, DeviceKind)] = &[
+    ("etrv", DeviceKind::RadiatorThermostat),
+    ("trv", DeviceKind::RadiatorThermostat),
+    ("icon", DeviceKind::IconController),
+];
+
+/// Whether `word` is the model `prefix`, optionally followed by a model
+/// number as in "eTRV0100" or "Icon2"
+fn is_model(word: &str, prefix: &str) -> bool {
+    word.strip_prefix(prefix)
+        .is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl Device {
+    /// Kind of the device
+    pub fn kind(&self) -> DeviceKind {
+        DeviceKind::from_device_type(&self.device_type)
+    }
+}
+
+impl AllyApi {
+    /// All cached devices of the given kind
+    pub fn devices_of_kind(&self, kind: &DeviceKind) -> Vec<&Device> {
+        self.devices
+            .iter()
+            .filter(|device| &device.kind() == kind)
+            .collect()
+    }
+
+    /// All radiator thermostats connected to the account
+    pub fn thermostats(&self) -> Vec<&Device> {
+        self.devices_of_kind(&DeviceKind::RadiatorThermostat)
+    }
+
+    /// All room sensors connected to the account
+    pub fn room_sensors(&self) -> Vec<&Device> {
+        self.devices_of_kind(&DeviceKind::RoomSensor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_types_map_to_kinds() {
+        let cases = [
+            (
+                "Danfoss Ally™ Radiator Thermostat",
+                DeviceKind::RadiatorThermostat,
+            ),
+            ("eTRV0100", DeviceKind::RadiatorThermostat),
+            ("Danfoss TRV", DeviceKind::RadiatorThermostat),
+            ("Danfoss Ally™ Room Sensor", DeviceKind::RoomSensor),
+            ("Danfoss Icon Room Sensor", DeviceKind::RoomSensor),
+            ("Danfoss Icon Room Thermostat", DeviceKind::IconController),
+            ("Icon2 Main Controller", DeviceKind::IconController),
+            ("Danfoss Ally™ Gateway", DeviceKind::Gateway),
+            (
+                "Danfoss Ally™ Electric Heat Plug",
+                DeviceKind::ElectricHeatPlug,
+            ),
+        ];
+        for (device_type, kind) in cases {
+            assert_eq!(
+                DeviceKind::from_device_type(device_type),
+                kind,
+                "{}",
+                device_type
+            );
+        }
+    }
+
+    #[test]
+    fn words_containing_a_name_are_unknown() {
+        for device_type in [
+            "Siliconeheat Controller",
+            "Plugin Hub",
+            "Gateways",
+            "Strvx",
+            "Iconic 3",
+        ] {
+            assert_eq!(
+                DeviceKind::from_device_type(device_type),
+                DeviceKind::Unknown(device_type.to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    bool_value, BannerState, Device, FaultCode, Mode, StatusCode, TemperatureUnit, WindowState,
+    WorkState,
+};
+use serde::{Deserialize, Serialize};
+
+/// Typed view of the status values of a device, see [`Device::typed_status`]
+///
+/// Every field is `None` if the device does not report the code.
+/// Temperatures are in degrees Celsius.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceStatus {
+    /// Measured temperature
+    pub current_temperature: Option<f32>,
+    /// Target temperature
+    pub setpoint: Option<f32>,
+    /// Relative humidity in percent
+    pub humidity: Option<f32>,
+    /// Battery level in percent
+    pub battery: Option<u8>,
+    /// Operating mode
+    pub mode: Option<Mode>,
+    /// Whether an open window is detected
+    pub window_open: Option<bool>,
+    /// Whether open window detection is enabled
+    pub window_detection: Option<bool>,
+    /// Whether the child lock is engaged
+    pub child_lock: Option<bool>,
+    /// What the thermostat is currently doing
+    pub work_state: Option<WorkState>,
+    /// Valve opening in percent
+    pub valve_position: Option<f32>,
+    /// Calibration offset
+    pub temperature_offset: Option<f32>,
+    /// Temperature pushed by an external room sensor
+    pub external_temperature: Option<f32>,
+    /// Whether the radiator is marked as covered
+    pub radiator_covered: Option<bool>,
+    /// Whether a switchable device is on
+    pub switch: Option<bool>,
+    /// Warning banner state
+    pub banner: Option<BannerState>,
+    /// Reported faults, empty if there are none
+    pub faults: Vec<FaultCode>,
+    /// Firmware version
+    pub firmware_version: Option<String>,
+}
+
+impl Device {
+    /// Interpret the raw status values into a [`DeviceStatus`]
+    pub fn typed_status(&self) -> DeviceStatus {
+        let unit = TemperatureUnit::Celsius;
+        DeviceStatus {
+            current_temperature: self.current_temperature(unit),
+            setpoint: self.setpoint(unit),
+            humidity: self.humidity(),
+            battery: self.battery(),
+            mode: self.mode(),
+            window_open: self.window_state().map(|state| state == WindowState::Open),
+            window_detection: self
+                .status_value(StatusCode::WindowToggle)
+                .and_then(bool_value),
+            child_lock: self.child_lock(),
+            work_state: self.work_state(),
+            valve_position: self.valve_position(),
+            temperature_offset: self.temperature_offset(),
+            external_temperature: self.external_temperature(unit),
+            radiator_covered: self.radiator_covered(),
+            switch: self.switch_state(),
+            banner: self.banner(),
+            faults: self.faults(),
+            firmware_version: self.firmware_version(),
+        }
+    }
+}
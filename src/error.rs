@@ -0,0 +1,219 @@
+use crate::protocol::{InvalidDeviceId, TOKEN_PATH};
+use crate::{
+    redact_body, ApiError, CommandRejected, ConfigError, InvalidHoliday, InvalidSetpoint,
+    OutOfRange, QuotaExceeded, Unsupported,
+};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Characters of a response body kept in [`AllyError::Deserialize`]
+pub const MAX_ERROR_BODY_CHARS: usize = 512;
+
+/// Error of an API operation
+///
+/// Errors of public operations are wrapped in [`AllyError::Correlated`];
+/// use [`AllyError::inner`] to match on the kind of failure.
+#[derive(Debug, Error)]
+pub enum AllyError {
+    /// The request could not be sent or the response not received
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// A request could not be built, e.g. because of an invalid header name
+    #[error("Cannot build the request: {0}")]
+    Request(#[from] http::Error),
+    /// A custom [`HttpTransport`](crate::HttpTransport) failed to send the
+    /// request or receive the response
+    #[error("Transport failed: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// The token endpoint rejected the credentials
+    #[error("Authentication failed: {0}")]
+    Auth(Box<ApiError>),
+    /// The access token was rejected (401), even after fetching a new one
+    #[error("Unauthorized: {0}")]
+    Unauthorized(Box<ApiError>),
+    /// The account may not access the resource (403)
+    #[error("Forbidden: {0}")]
+    Forbidden(Box<ApiError>),
+    /// The device or endpoint does not exist (404)
+    #[error("Not found: {0}")]
+    NotFound(Box<ApiError>),
+    /// The API throttled the request
+    #[error("Rate limited: {error}")]
+    RateLimited {
+        /// How long to wait before retrying, if the API said so
+        retry_after: Option<Duration>,
+        /// The throttled response
+        error: Box<ApiError>,
+    },
+    /// The API failed to handle the request (5xx)
+    #[error("Server error: {0}")]
+    ServerError(Box<ApiError>),
+    /// The API answered with another unsuccessful HTTP status
+    #[error(transparent)]
+    Api(Box<ApiError>),
+    /// A request body could not be encoded
+    #[error("Cannot encode the request: {0}")]
+    Encode(serde_json::Error),
+    /// The response body does not have the expected format
+    #[error("Cannot read the response of {path}: {source}; body: {body}")]
+    Deserialize {
+        /// API path that was requested
+        path: String,
+        /// The response body, with secrets redacted and truncated to
+        /// [`MAX_ERROR_BODY_CHARS`] characters
+        body: String,
+        /// Underlying error
+        source: serde_json::Error,
+    },
+    /// The client configuration is incomplete
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    /// The request quota is used up
+    #[error(transparent)]
+    QuotaExceeded(#[from] QuotaExceeded),
+    /// The API did not apply a command
+    #[error(transparent)]
+    CommandRejected(#[from] CommandRejected),
+    /// The device does not support a command
+    #[error(transparent)]
+    Unsupported(#[from] Unsupported),
+    /// A command value is outside the accepted range
+    #[error(transparent)]
+    OutOfRange(#[from] OutOfRange),
+    /// A setpoint is not accepted by the device
+    #[error(transparent)]
+    InvalidSetpoint(#[from] InvalidSetpoint),
+    /// A holiday period is not valid
+    #[error(transparent)]
+    InvalidHoliday(#[from] InvalidHoliday),
+    /// A device id cannot be used in a request path
+    #[error(transparent)]
+    InvalidDeviceId(#[from] InvalidDeviceId),
+    /// Requests are paused after repeated failures, see
+    /// [`CircuitBreaker`](crate::CircuitBreaker)
+    #[error("Requests paused after repeated failures, retrying in {retry_in:?}")]
+    CircuitOpen {
+        /// Time until the next request is let through, or until the trial
+        /// request of a half-open circuit has likely finished
+        retry_in: Duration,
+    },
+    /// The operation did not finish in time
+    #[error("Operation did not finish within {0:?}")]
+    Timeout(Duration),
+    /// Failure of an operation, tagged with the correlation id that also
+    /// appears in the log lines of that operation
+    #[error("[{correlation_id}] {source}")]
+    Correlated {
+        /// Correlation id of the failed operation
+        correlation_id: String,
+        /// The underlying error
+        source: Box<AllyError>,
+    },
+}
+
+impl AllyError {
+    pub(crate) fn correlated(correlation_id: &str, source: AllyError) -> Self {
+        AllyError::Correlated {
+            correlation_id: correlation_id.to_string(),
+            source: Box::new(source),
+        }
+    }
+
+    pub(crate) fn deserialize(path: &str, body: &str, source: serde_json::Error) -> Self {
+        let body = if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+            redact_body(body)
+        } else {
+            body.to_string()
+        };
+        let body = match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+            Some((end, _)) => format!("{}... ({} bytes)", &body[..end], body.len()),
+            None => body,
+        };
+        AllyError::Deserialize {
+            path: path.to_string(),
+            body,
+            source,
+        }
+    }
+
+    /// The error without the correlation id
+    pub fn inner(&self) -> &AllyError {
+        match self {
+            AllyError::Correlated { source, .. } => source.inner(),
+            other => other,
+        }
+    }
+
+    /// Whether the failure is likely temporary, so the request may succeed
+    /// when sent again: timeouts, connection failures, throttling and
+    /// server errors
+    pub fn is_transient(&self) -> bool {
+        match self.inner() {
+            AllyError::Http(e) => e.is_timeout() || connection_failed(e),
+            AllyError::RateLimited { .. } | AllyError::ServerError(_) => true,
+            _ => false,
+        }
+    }
+
+    /// How long the API asked to wait before sending the request again
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.api_error()?.retry_after
+    }
+
+    /// The API error response behind this error, if any
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self.inner() {
+            AllyError::Auth(error)
+            | AllyError::Unauthorized(error)
+            | AllyError::Forbidden(error)
+            | AllyError::NotFound(error)
+            | AllyError::RateLimited { error, .. }
+            | AllyError::ServerError(error)
+            | AllyError::Api(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the API error response behind this error, if any
+    pub fn status(&self) -> Option<u16> {
+        self.api_error().map(|error| error.status)
+    }
+
+    /// Correlation id of the failed operation, if known
+    pub fn correlation_id(&self) -> Option<&str> {
+        match self {
+            AllyError::Correlated { correlation_id, .. } => Some(correlation_id),
+            _ => None,
+        }
+    }
+}
+
+impl From<ApiError> for AllyError {
+    fn from(error: ApiError) -> Self {
+        match error.status {
+            400 | 401 if error.path == TOKEN_PATH => AllyError::Auth(Box::new(error)),
+            401 => AllyError::Unauthorized(Box::new(error)),
+            403 => AllyError::Forbidden(Box::new(error)),
+            404 => AllyError::NotFound(Box::new(error)),
+            429 if !error.is_quota_exhausted() => AllyError::RateLimited {
+                retry_after: error.retry_after,
+                error: Box::new(error),
+            },
+            _ if error.is_server_error() => AllyError::ServerError(Box::new(error)),
+            _ => AllyError::Api(Box::new(error)),
+        }
+    }
+}
+
+/// Whether a request failed to reach the server
+#[cfg(not(target_arch = "wasm32"))]
+fn connection_failed(error: &reqwest::Error) -> bool {
+    error.is_connect()
+}
+
+/// Whether a request failed to reach the server. The browser does not tell
+/// connection failures apart from other failed fetches.
+#[cfg(target_arch = "wasm32")]
+fn connection_failed(error: &reqwest::Error) -> bool {
+    error.is_request()
+}
@@ -0,0 +1,334 @@
+use crate::{Device, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Events emitted by [`AllyApi`](crate::AllyApi) when device state changes between polls
+///
+/// Events are queued by [`AllyApi::get_devices`](crate::AllyApi::get_devices) and can be
+/// collected with [`AllyApi::take_events`](crate::AllyApi::take_events).
+///
+/// Events serialize to a flat JSON object tagged with an `event` field, so
+/// they can be written one per line as NDJSON:
+///
+/// ```
+/// use danfoss_ally_rs::DeviceEvent;
+///
+/// let event = DeviceEvent::FirmwareChanged {
+///     device_id: "abc".to_string(),
+///     old: Some("1.0".to_string()),
+///     new: Some("1.1".to_string()),
+/// };
+/// assert_eq!(
+///     serde_json::to_string(&event).unwrap(),
+///     r#"{"event":"firmware_changed","device_id":"abc","old":"1.0","new":"1.1"}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DeviceEvent {
+    /// The firmware version reported by a device changed
+    FirmwareChanged {
+        /// Unique identifier of the device
+        device_id: String,
+        /// Firmware version seen in the previous poll
+        old: Option<String>,
+        /// Firmware version seen in the current poll
+        new: Option<String>,
+    },
+    /// One or more status values of a device changed
+    StatusChanged(DeviceDiff),
+}
+
+/// Change of a single status code between two device states
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange {
+    /// Status code
+    pub code: StatusCode,
+    /// Previous value, `None` if the code was not reported before
+    pub old: Option<Value>,
+    /// New value, `None` if the code is not reported anymore
+    pub new: Option<Value>,
+}
+
+/// Status codes that differ between two states of the same device
+///
+/// ```
+/// use danfoss_ally_rs::{Device, DeviceDiff, Status, StatusCode};
+/// use serde_json::json;
+///
+/// let old = Device {
+///     id: "abc".to_string(),
+///     status: vec![Status { code: StatusCode::TempSet, value: json!(200) }],
+///     ..Default::default()
+/// };
+/// let mut new = old.clone();
+/// new.status[0].value = json!(215);
+///
+/// let diff = DeviceDiff::between(&old, &new);
+/// assert_eq!(diff.changes.len(), 1);
+/// assert_eq!(diff.change(StatusCode::TempSet).unwrap().new, Some(json!(215)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceDiff {
+    /// Unique identifier of the device
+    pub device_id: String,
+    /// Name of the device in the newer state
+    pub device_name: String,
+    /// Changed status codes, in the order the new state reports them
+    pub changes: Vec<StatusChange>,
+}
+
+impl DeviceDiff {
+    /// Compare two states of a device
+    pub fn between(old: &Device, new: &Device) -> Self {
+        let mut changes: Vec<StatusChange> = new
+            .status
+            .iter()
+            .filter_map(|status| {
+                let previous = old.status_value(&status.code);
+                (previous != Some(&status.value)).then(|| StatusChange {
+                    code: status.code.clone(),
+                    old: previous.cloned(),
+                    new: Some(status.value.clone()),
+                })
+            })
+            .collect();
+        changes.extend(
+            old.status
+                .iter()
+                .filter(|status| new.status_value(&status.code).is_none())
+                .map(|status| StatusChange {
+                    code: status.code.clone(),
+                    old: Some(status.value.clone()),
+                    new: None,
+                }),
+        );
+        Self {
+            device_id: new.id.clone(),
+            device_name: new.name.clone(),
+            changes,
+        }
+    }
+
+    /// Whether both states report the same status values
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The change of a specific status code, if it changed
+    pub fn change(&self, code: impl Into<StatusCode>) -> Option<&StatusChange> {
+        let code = code.into();
+        self.changes.iter().find(|change| change.code == code)
+    }
+}
+
+impl fmt::Display for DeviceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~ {} ({})", self.device_name, self.device_id)?;
+        for change in &self.changes {
+            write!(
+                f,
+                "\n    {}: {} -> {}",
+                change.code,
+                display_change(&change.old),
+                display_change(&change.new)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Differences between two snapshots of all devices of an account
+///
+/// The `Display` output is a readable report with one block per device.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    /// Devices only present in the newer snapshot
+    pub added: Vec<Device>,
+    /// Devices only present in the older snapshot
+    pub removed: Vec<Device>,
+    /// Devices present in both snapshots whose status differs
+    pub changed: Vec<DeviceDiff>,
+}
+
+impl SnapshotDiff {
+    /// Compare two device lists, matching devices by id
+    pub fn between(old: &[Device], new: &[Device]) -> Self {
+        let find = |devices: &[Device], id: &str| devices.iter().position(|d| d.id == id);
+        Self {
+            added: new
+                .iter()
+                .filter(|device| find(old, &device.id).is_none())
+                .cloned()
+                .collect(),
+            removed: old
+                .iter()
+                .filter(|device| find(new, &device.id).is_none())
+                .cloned()
+                .collect(),
+            changed: new
+                .iter()
+                .filter_map(|device| {
+                    let previous = &old[find(old, &device.id)?];
+                    let diff = DeviceDiff::between(previous, device);
+                    (!diff.is_empty()).then_some(diff)
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether both snapshots are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        let mut lines: Vec<String> = vec![];
+        lines.extend(
+            self.added
+                .iter()
+                .map(|device| format!("+ {} ({})", device.name, device.id)),
+        );
+        lines.extend(
+            self.removed
+                .iter()
+                .map(|device| format!("- {} ({})", device.name, device.id)),
+        );
+        lines.extend(self.changed.iter().map(DeviceDiff::to_string));
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn display_change(value: &Option<Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+        None => "(none)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use serde_json::json;
+
+    fn device(id: &str, status: &[(StatusCode, Value)]) -> Device {
+        Device {
+            id: id.to_string(),
+            name: format!("Device {}", id),
+            status: status
+                .iter()
+                .map(|(code, value)| Status {
+                    code: code.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn unchanged_status_gives_an_empty_diff() {
+        let state = device("abc", &[(StatusCode::TempSet, json!(215))]);
+        assert!(DeviceDiff::between(&state, &state.clone()).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed_codes() {
+        let old = device(
+            "abc",
+            &[
+                (StatusCode::TempSet, json!(200)),
+                (StatusCode::ChildLock, json!(false)),
+            ],
+        );
+        let new = device(
+            "abc",
+            &[
+                (StatusCode::TempSet, json!(215)),
+                (StatusCode::TempCurrent, json!(198)),
+            ],
+        );
+
+        let diff = DeviceDiff::between(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                StatusChange {
+                    code: StatusCode::TempSet,
+                    old: Some(json!(200)),
+                    new: Some(json!(215)),
+                },
+                StatusChange {
+                    code: StatusCode::TempCurrent,
+                    old: None,
+                    new: Some(json!(198)),
+                },
+                StatusChange {
+                    code: StatusCode::ChildLock,
+                    old: Some(json!(false)),
+                    new: None,
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "~ Device abc (abc)\n    temp_set: 200 -> 215\n    \
+             temp_current: (none) -> 198\n    child_lock: false -> (none)"
+        );
+    }
+
+    #[test]
+    fn reordered_status_is_not_a_change() {
+        let old = device(
+            "abc",
+            &[
+                (StatusCode::TempSet, json!(200)),
+                (StatusCode::ChildLock, json!(true)),
+            ],
+        );
+        let mut new = old.clone();
+        new.status.reverse();
+        assert!(DeviceDiff::between(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn snapshot_diff_matches_devices_by_id() {
+        let kept = device("a", &[(StatusCode::TempSet, json!(200))]);
+        let mut changed = kept.clone();
+        changed.status[0].value = json!(210);
+        let unchanged = device("b", &[]);
+        let removed = device("c", &[]);
+        let added = device("d", &[]);
+
+        let diff = SnapshotDiff::between(
+            &[kept, unchanged.clone(), removed.clone()],
+            &[added.clone(), unchanged, changed],
+        );
+
+        assert_eq!(diff.added, vec![added]);
+        assert_eq!(diff.removed, vec![removed]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].device_id, "a");
+        assert_eq!(
+            diff.to_string(),
+            "+ Device d (d)\n- Device c (c)\n~ Device a (a)\n    temp_set: 200 -> 210"
+        );
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let devices = [device("a", &[(StatusCode::TempSet, json!(200))])];
+        let diff = SnapshotDiff::between(&devices, &devices);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "No changes");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{
+    numeric_value, AllyApi, AllyError, Command, Device, OutOfRange, StatusCode, TemperatureUnit,
+};
+use serde_json::Value;
+
+/// Value of `ext_measured_rs` that means no external temperature is set
+const NO_EXTERNAL_TEMPERATURE: i32 = -8000;
+
+/// Lowest and highest room temperature in degrees Celsius that is pushed to
+/// a thermostat; anything outside is a faulty reading
+const EXTERNAL_TEMPERATURE_RANGE: (f32, f32) = (-20.0, 50.0);
+
+impl Device {
+    /// Temperature last pushed by an external room sensor
+    /// (`ext_measured_rs`), if one is set
+    pub fn external_temperature(&self, unit: TemperatureUnit) -> Option<f32> {
+        self.status_value(StatusCode::ExtMeasuredRs)
+            .and_then(numeric_value)
+            .filter(|raw| raw.round() as i32 != NO_EXTERNAL_TEMPERATURE)
+            .map(|raw| unit.decode_deci_celsius(raw))
+    }
+
+    /// Room sensor linked to the device (`room_sensor`), if any
+    pub fn room_sensor(&self) -> Option<String> {
+        match self.status_value(StatusCode::RoomSensor)? {
+            Value::String(sensor) if !sensor.is_empty() => Some(sensor.clone()),
+            Value::Number(sensor) => Some(sensor.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl AllyApi {
+    /// Push a temperature measured by an external room sensor to a
+    /// thermostat, which then regulates against it instead of its own
+    /// reading.
+    ///
+    /// Thermostats fall back to their own reading if no value arrives for a
+    /// while, so this should be repeated regularly, e.g. every few minutes.
+    ///
+    /// Fails with [`OutOfRange`] without sending a request if the reading is
+    /// not a number or outside of -20 to 50 °C.
+    pub async fn push_external_temperature(
+        &self,
+        device_id: &str,
+        celsius: f32,
+    ) -> Result<(), AllyError> {
+        let (min, max) = EXTERNAL_TEMPERATURE_RANGE;
+        OutOfRange::check(StatusCode::ExtMeasuredRs, celsius, min, max)?;
+        let value = TemperatureUnit::Celsius.encode_deci_celsius(celsius);
+        self.send_commands(
+            device_id,
+            vec![Command::new(StatusCode::ExtMeasuredRs, value)],
+        )
+        .await
+    }
+
+    /// Stop regulating a thermostat against an external temperature
+    pub async fn clear_external_temperature(&self, device_id: &str) -> Result<(), AllyError> {
+        self.send_commands(
+            device_id,
+            vec![Command::new(
+                StatusCode::ExtMeasuredRs,
+                NO_EXTERNAL_TEMPERATURE,
+            )],
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn faulty_readings_are_not_sent() {
+        let api = AllyApi::default();
+        for celsius in [f32::NAN, f32::INFINITY, -800.0, -20.5, 50.5] {
+            match api.push_external_temperature("abc", celsius).await {
+                Err(AllyError::OutOfRange(error)) => {
+                    assert_eq!(error.code, StatusCode::ExtMeasuredRs)
+                }
+                other => panic!("{} gave {:?}", celsius, other),
+            }
+        }
+    }
+
+    #[test]
+    fn sentinel_means_no_external_temperature() {
+        let mut device = Device {
+            status: vec![Status {
+                code: StatusCode::ExtMeasuredRs,
+                value: json!(NO_EXTERNAL_TEMPERATURE),
+            }],
+            ..Device::default()
+        };
+        assert_eq!(device.external_temperature(TemperatureUnit::Celsius), None);
+
+        device.status[0].value = json!(195);
+        assert_eq!(
+            device.external_temperature(TemperatureUnit::Celsius),
+            Some(19.5)
+        );
+    }
+}
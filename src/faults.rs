@@ -0,0 +1,75 @@
+use crate::{numeric_value, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// A fault reported by a thermostat in the `fault` bit field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultCode {
+    /// Temperature sensor on the top circuit board failed (bit 0)
+    TopSensorError,
+    /// Temperature sensor on the side circuit board failed (bit 1)
+    SideSensorError,
+    /// Non-volatile memory failed (bit 2)
+    MemoryError,
+    /// Unspecified hardware error (bit 3)
+    HardwareError,
+    /// Valve motor failed (bit 5)
+    MotorError,
+    /// Internal communication between components failed (bit 7)
+    InternalCommunicationError,
+    /// Clock information is invalid (bit 9)
+    InvalidClock,
+    /// Radio communication failed (bit 11)
+    RadioError,
+    /// The valve encoder is jammed (bit 12)
+    EncoderJammed,
+    /// Battery is low (bit 13)
+    LowBattery,
+    /// Battery is critically low (bit 14)
+    CriticalBattery,
+    /// A bit not known to this crate
+    Unknown(u8),
+}
+
+impl FaultCode {
+    /// Decode the `fault` bit field into the individual faults
+    ///
+    /// ```
+    /// use danfoss_ally_rs::FaultCode;
+    ///
+    /// assert_eq!(
+    ///     FaultCode::decode(0b10_0000_0010_0000),
+    ///     vec![FaultCode::MotorError, FaultCode::LowBattery]
+    /// );
+    /// assert!(FaultCode::decode(0).is_empty());
+    /// ```
+    pub fn decode(bits: u32) -> Vec<FaultCode> {
+        (0..32)
+            .filter(|bit| bits & (1 << bit) != 0)
+            .map(|bit| match bit {
+                0 => FaultCode::TopSensorError,
+                1 => FaultCode::SideSensorError,
+                2 => FaultCode::MemoryError,
+                3 => FaultCode::HardwareError,
+                5 => FaultCode::MotorError,
+                7 => FaultCode::InternalCommunicationError,
+                9 => FaultCode::InvalidClock,
+                11 => FaultCode::RadioError,
+                12 => FaultCode::EncoderJammed,
+                13 => FaultCode::LowBattery,
+                14 => FaultCode::CriticalBattery,
+                bit => FaultCode::Unknown(bit),
+            })
+            .collect()
+    }
+}
+
+impl Device {
+    /// Faults currently reported by the device, empty if there are none
+    pub fn faults(&self) -> Vec<FaultCode> {
+        self.status_value(StatusCode::Fault)
+            .and_then(numeric_value)
+            .map(|bits| FaultCode::decode(bits.max(0.0) as u32))
+            .unwrap_or_default()
+    }
+}
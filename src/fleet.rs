@@ -0,0 +1,117 @@
+use crate::config::credential_from_env;
+use crate::{AllyApi, AllyError, ConfigError, Device};
+use futures::future;
+use std::collections::BTreeMap;
+
+/// Tag carrying the account name on every device of an [`AllyFleet`]
+pub const ACCOUNT_TAG: &str = "account";
+
+/// Per-account results of [`AllyFleet::get_devices`], keyed by account name
+pub type FleetResults = BTreeMap<String, Result<(), AllyError>>;
+
+/// A device together with the account it belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FleetDevice<'a> {
+    /// Name of the account
+    pub account: &'a str,
+    /// The device
+    pub device: &'a Device,
+}
+
+/// Clients for several Danfoss accounts, e.g. one per property
+///
+/// Every account keeps its own credentials, token and quota. Devices of an
+/// account carry its name in the [`ACCOUNT_TAG`] tag.
+#[derive(Debug, Default)]
+pub struct AllyFleet {
+    accounts: BTreeMap<String, AllyApi>,
+}
+
+impl AllyApi {
+    /// Create new danfoss ally client with the credentials of a named
+    /// account from the `DANFOSS_<ACCOUNT>_API_KEY` and
+    /// `DANFOSS_<ACCOUNT>_API_SECRET` environment variables. The account name
+    /// is upper-cased and dashes become underscores.
+    pub fn try_new_for_account(account: &str) -> Result<Self, ConfigError> {
+        let prefix = format!("DANFOSS_{}", account.to_uppercase().replace('-', "_"));
+        let api_key = credential_from_env(&format!("{}_API_KEY", prefix))?;
+        let api_secret = credential_from_env(&format!("{}_API_SECRET", prefix))?;
+        Ok(Self::with_credentials(api_key, api_secret))
+    }
+}
+
+impl AllyFleet {
+    /// Create an empty fleet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the client of an account, replacing a previous one of the same name
+    pub fn add_account(&mut self, name: &str, mut api: AllyApi) {
+        tag_devices(name, &mut api);
+        self.accounts.insert(name.to_string(), api);
+    }
+
+    /// Remove an account and return its client
+    pub fn remove_account(&mut self, name: &str) -> Option<AllyApi> {
+        self.accounts.remove(name)
+    }
+
+    /// Client of an account
+    pub fn account(&self, name: &str) -> Option<&AllyApi> {
+        self.accounts.get(name)
+    }
+
+    /// Mutable client of an account, e.g. to send commands
+    pub fn account_mut(&mut self, name: &str) -> Option<&mut AllyApi> {
+        self.accounts.get_mut(name)
+    }
+
+    /// Names of all accounts, sorted
+    pub fn account_names(&self) -> Vec<&str> {
+        self.accounts.keys().map(String::as_str).collect()
+    }
+
+    /// Poll the devices of all accounts concurrently. Returns the result of
+    /// every account; a failing account keeps its previous devices.
+    pub async fn get_devices(&mut self) -> FleetResults {
+        future::join_all(self.accounts.iter_mut().map(|(name, api)| async move {
+            let result = api.get_devices().await;
+            tag_devices(name, api);
+            (name.clone(), result)
+        }))
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    /// Devices of all accounts, ordered by account name
+    pub fn devices(&self) -> Vec<FleetDevice<'_>> {
+        self.accounts
+            .iter()
+            .flat_map(|(name, api)| {
+                api.devices.iter().map(move |device| FleetDevice {
+                    account: name,
+                    device,
+                })
+            })
+            .collect()
+    }
+
+    /// Look up a device by id in all accounts
+    pub fn find_device(&self, device_id: &str) -> Option<FleetDevice<'_>> {
+        self.devices()
+            .into_iter()
+            .find(|entry| entry.device.id == device_id)
+    }
+}
+
+/// Set the account tag on every device of a client
+fn tag_devices(name: &str, api: &mut AllyApi) {
+    for device in &api.devices {
+        api.tags
+            .entry(device.id.clone())
+            .or_default()
+            .insert(ACCOUNT_TAG.to_string(), name.to_string());
+    }
+}
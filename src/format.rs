@@ -0,0 +1,245 @@
+use crate::{Device, DeviceKind, DevicesResponse, Status, StatusCode, TemperatureUnit};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.code, display_value(&self.value))
+    }
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceKind::RadiatorThermostat => f.write_str("radiator thermostat"),
+            DeviceKind::RoomSensor => f.write_str("room sensor"),
+            DeviceKind::IconController => f.write_str("Icon controller"),
+            DeviceKind::Gateway => f.write_str("gateway"),
+            DeviceKind::ElectricHeatPlug => f.write_str("electric heat plug"),
+            DeviceKind::Unknown(device_type) => f.write_str(device_type),
+        }
+    }
+}
+
+/// One line per device, e.g. `Kitchen [radiator thermostat] online, 20.5 °C -> 21.0 °C, battery 78 %`
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            self.name,
+            self.kind(),
+            if self.online { "online" } else { "offline" }
+        )?;
+        let unit = TemperatureUnit::Celsius;
+        match (self.current_temperature(unit), self.setpoint(unit)) {
+            (Some(current), Some(setpoint)) => write!(
+                f,
+                ", {} -> {}",
+                humanize_temperature(current, None, unit),
+                humanize_temperature(setpoint, None, unit)
+            )?,
+            (Some(current), None) => write!(f, ", {}", humanize_temperature(current, None, unit))?,
+            (None, Some(setpoint)) => {
+                write!(f, ", -> {}", humanize_temperature(setpoint, None, unit))?
+            }
+            (None, None) => {}
+        }
+        if let Some(battery) = self.battery() {
+            write!(f, ", battery {} %", battery)?;
+        }
+        Ok(())
+    }
+}
+
+impl DevicesResponse {
+    /// Text table of all devices with their kind, state, temperatures and
+    /// battery level, see [`render_table`]
+    pub fn summary(&self) -> String {
+        render_table(
+            &self.result,
+            &[
+                Column::Name,
+                Column::Kind,
+                Column::Online,
+                Column::Temperature,
+                Column::Setpoint,
+                Column::Battery,
+            ],
+        )
+    }
+}
+
+/// A column of the table produced by [`render_table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    /// Unique identifier of the device
+    Id,
+    /// User specified name of the device
+    Name,
+    /// Type of device
+    DeviceType,
+    /// Kind of device, see [`DeviceKind`]
+    Kind,
+    /// Online status of the device
+    Online,
+    /// Measured temperature in degrees Celsius
+    Temperature,
+    /// Target temperature in degrees Celsius
+    Setpoint,
+    /// Battery level in percent
+    Battery,
+    /// Value of the status with the given code
+    Status(StatusCode),
+}
+
+impl Column {
+    fn header(&self) -> &str {
+        match self {
+            Column::Id => "ID",
+            Column::Name => "NAME",
+            Column::DeviceType => "TYPE",
+            Column::Kind => "KIND",
+            Column::Online => "ONLINE",
+            Column::Temperature => "TEMP",
+            Column::Setpoint => "SETPOINT",
+            Column::Battery => "BATTERY",
+            Column::Status(code) => code.as_str(),
+        }
+    }
+
+    fn cell(&self, device: &Device) -> String {
+        match self {
+            Column::Id => device.id.clone(),
+            Column::Name => device.name.clone(),
+            Column::DeviceType => device.device_type.clone(),
+            Column::Kind => device.kind().to_string(),
+            Column::Online => if device.online { "yes" } else { "no" }.to_string(),
+            Column::Temperature => device
+                .current_temperature(TemperatureUnit::Celsius)
+                .map(|value| format!("{:.1}", value))
+                .unwrap_or_default(),
+            Column::Setpoint => device
+                .setpoint(TemperatureUnit::Celsius)
+                .map(|value| format!("{:.1}", value))
+                .unwrap_or_default(),
+            Column::Battery => device
+                .battery()
+                .map(|level| format!("{} %", level))
+                .unwrap_or_default(),
+            Column::Status(code) => device
+                .status_value(code)
+                .map(display_value)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Render devices as an aligned text table with a header row
+///
+/// ```
+/// use danfoss_ally_rs::{render_table, Column, Device};
+///
+/// let device = Device {
+///     id: "abc".to_string(),
+///     name: "Kitchen".to_string(),
+///     online: true,
+///     ..Default::default()
+/// };
+/// let table = render_table(&[device], &[Column::Name, Column::Online]);
+/// assert_eq!(table, "NAME     ONLINE\nKitchen  yes\n");
+/// ```
+pub fn render_table(devices: &[Device], columns: &[Column]) -> String {
+    let rows: Vec<Vec<String>> =
+        std::iter::once(columns.iter().map(|c| c.header().to_string()).collect())
+            .chain(
+                devices
+                    .iter()
+                    .map(|device| columns.iter().map(|c| c.cell(device)).collect()),
+            )
+            .collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = String::new();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Describe how long ago something happened, e.g. `4 min ago`
+///
+/// ```
+/// use danfoss_ally_rs::humanize_ago;
+/// use std::time::Duration;
+///
+/// assert_eq!(humanize_ago(Duration::from_secs(250)), "4 min ago");
+/// assert_eq!(humanize_ago(Duration::from_secs(3 * 86400)), "3 days ago");
+/// ```
+pub fn humanize_ago(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        86400..=172799 => "1 day ago".to_string(),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+/// Describe a battery level, optionally with the remaining runtime estimated
+/// from the lifetime of a full battery, e.g. `78 % ≈ 9 months left`
+pub fn humanize_battery(percent: u8, full_lifetime: Option<Duration>) -> String {
+    match full_lifetime {
+        Some(lifetime) => {
+            let remaining = lifetime.as_secs_f64() * f64::from(percent.min(100)) / 100.0;
+            let months = (remaining / (30.0 * 86400.0)).round() as u64;
+            if months == 1 {
+                format!("{} % ≈ 1 month left", percent)
+            } else {
+                format!("{} % ≈ {} months left", percent, months)
+            }
+        }
+        None => format!("{} %", percent),
+    }
+}
+
+/// Format a temperature with its unit and a trend arrow relative to the
+/// previous reading, e.g. `21.5 °C ↑`
+///
+/// ```
+/// use danfoss_ally_rs::{humanize_temperature, TemperatureUnit};
+///
+/// let text = humanize_temperature(21.5, Some(21.0), TemperatureUnit::Celsius);
+/// assert_eq!(text, "21.5 °C ↑");
+/// ```
+pub fn humanize_temperature(value: f32, previous: Option<f32>, unit: TemperatureUnit) -> String {
+    let formatted = format!("{:.1} {}", value, unit.symbol());
+    match previous {
+        Some(previous) if value - previous > 0.05 => format!("{} ↑", formatted),
+        Some(previous) if previous - value > 0.05 => format!("{} ↓", formatted),
+        Some(_) => format!("{} →", formatted),
+        None => formatted,
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{AllyApi, AllyError};
+
+/// Lowest setpoint in degrees Celsius that still keeps pipes from freezing
+pub const FROST_PROTECTION_TEMPERATURE: f32 = 5.0;
+
+impl AllyApi {
+    /// Drop a thermostat to [`FROST_PROTECTION_TEMPERATURE`], e.g. to protect
+    /// an empty house.
+    ///
+    /// The previous setpoint is remembered for
+    /// [`AllyApi::restore_from_frost_protection`]. A running boost of the
+    /// device is dropped without being reverted.
+    pub async fn set_frost_protection(&self, device_id: &str) -> Result<(), AllyError> {
+        let _setpoint = self.lock_setpoint(device_id).await;
+        let protected = self.frost_protected_setpoint(device_id);
+        let previous = match protected.or_else(|| self.boost_of(device_id).map(|b| b.previous)) {
+            Some(previous) => previous,
+            None => self.current_setpoint(device_id).await?,
+        };
+        self.set_temperature(device_id, FROST_PROTECTION_TEMPERATURE)
+            .await?;
+        let mut shared = self.shared();
+        shared.boosts.retain(|boost| boost.device_id != device_id);
+        shared
+            .frost_protected
+            .insert(device_id.to_string(), previous);
+        Ok(())
+    }
+
+    /// Whether a device is held at frost protection by this client or a clone
+    /// of it
+    pub fn is_frost_protected(&self, device_id: &str) -> bool {
+        self.frost_protected_setpoint(device_id).is_some()
+    }
+
+    /// Setpoint in degrees Celsius a frost protected device had before
+    fn frost_protected_setpoint(&self, device_id: &str) -> Option<f32> {
+        self.shared().frost_protected.get(device_id).copied()
+    }
+
+    /// Restore the setpoint a device had before
+    /// [`AllyApi::set_frost_protection`]. Does nothing for devices that are
+    /// not frost protected.
+    pub async fn restore_from_frost_protection(&self, device_id: &str) -> Result<(), AllyError> {
+        let _setpoint = self.lock_setpoint(device_id).await;
+        let Some(previous) = self.frost_protected_setpoint(device_id) else {
+            return Ok(());
+        };
+        self.set_temperature(device_id, previous).await?;
+        self.shared().frost_protected.remove(device_id);
+        Ok(())
+    }
+}
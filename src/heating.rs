@@ -0,0 +1,41 @@
+use crate::{numeric_value, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What a thermostat is currently doing (`work_state`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkState {
+    /// The valve is open and the radiator is heating
+    Heat,
+    /// The setpoint is reached and the valve is closed
+    Idle,
+    /// A state not known to this crate, as reported
+    Other(String),
+}
+
+impl Device {
+    /// Current work state, if reported
+    pub fn work_state(&self) -> Option<WorkState> {
+        match self.status_value(StatusCode::WorkState)? {
+            Value::String(state) => Some(match state.trim().to_ascii_lowercase().as_str() {
+                "heat" | "heating" => WorkState::Heat,
+                "idle" | "standby" => WorkState::Idle,
+                _ => WorkState::Other(state.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the device is currently heating
+    pub fn is_heating(&self) -> bool {
+        self.work_state() == Some(WorkState::Heat)
+    }
+
+    /// Valve opening in percent (`valve_position`), if reported
+    pub fn valve_position(&self) -> Option<f32> {
+        self.status_value(StatusCode::ValvePosition)
+            .and_then(numeric_value)
+            .map(|position| position.clamp(0.0, 100.0))
+    }
+}
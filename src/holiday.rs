@@ -0,0 +1,92 @@
+use crate::runtime::{SystemTime, UNIX_EPOCH};
+use crate::{
+    numeric_value, AllyApi, AllyError, Command, Device, Mode, StatusCode, TemperatureUnit,
+};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// A holiday period during which a thermostat holds a fixed temperature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Holiday {
+    /// Start of the holiday
+    pub start: SystemTime,
+    /// End of the holiday, after which the weekly schedule resumes
+    pub end: SystemTime,
+    /// Temperature to hold in degrees Celsius
+    pub temperature: f32,
+}
+
+/// Error returned when a holiday does not end after it starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidHoliday {
+    /// Requested start
+    pub start: SystemTime,
+    /// Requested end
+    pub end: SystemTime,
+}
+
+impl fmt::Display for InvalidHoliday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Holiday ends before it starts")
+    }
+}
+
+impl Error for InvalidHoliday {}
+
+impl Device {
+    /// Programmed holiday, if the device reports one
+    pub fn holiday(&self) -> Option<Holiday> {
+        let time = |code: StatusCode| {
+            self.status_value(code)
+                .and_then(numeric_value)
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds.max(0.0) as u64))
+        };
+        Some(Holiday {
+            start: time(StatusCode::HolidayStart)?,
+            end: time(StatusCode::HolidayEnd)?,
+            temperature: self
+                .status_value(StatusCode::HolidaySetting)
+                .and_then(numeric_value)
+                .map(|raw| TemperatureUnit::Celsius.decode_deci_celsius(raw))?,
+        })
+    }
+}
+
+impl AllyApi {
+    /// Program a holiday, e.g. away from Dec 20 to Jan 3 at 15 °C.
+    ///
+    /// The thermostat switches to [`Mode::HolidaySatAway`] and holds the
+    /// holiday temperature until the end of the period.
+    pub async fn set_holiday(&self, device_id: &str, holiday: &Holiday) -> Result<(), AllyError> {
+        let seconds = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+        if holiday.end <= holiday.start {
+            return Err(InvalidHoliday {
+                start: holiday.start,
+                end: holiday.end,
+            }
+            .into());
+        }
+        let temperature = TemperatureUnit::Celsius.encode_deci_celsius(holiday.temperature);
+        let commands = vec![
+            Command::new(StatusCode::HolidayStart, seconds(holiday.start)),
+            Command::new(StatusCode::HolidayEnd, seconds(holiday.end)),
+            Command::new(StatusCode::HolidaySetting, temperature),
+            Command::new(
+                StatusCode::Mode,
+                serde_json::to_value(Mode::HolidaySatAway).map_err(AllyError::Encode)?,
+            ),
+        ];
+        self.send_commands(device_id, commands).await
+    }
+
+    /// End a holiday early and return to the weekly schedule
+    pub async fn cancel_holiday(&self, device_id: &str) -> Result<(), AllyError> {
+        self.set_mode(device_id, Mode::AtHome).await
+    }
+}
@@ -1,64 +1,506 @@
-use base64;
-use log::*;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::env;
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+#[macro_use]
+mod logging;
+
+mod api_error;
+mod availability;
+mod banner;
+mod boost;
+mod builder;
+mod capabilities;
+mod circuit_breaker;
+mod commands;
+mod config;
+mod controls;
+mod correlation;
+mod device_kind;
+mod device_status;
+mod error;
+mod events;
+mod external_sensor;
+mod faults;
+mod fleet;
+mod format;
+mod frost;
+mod heating;
+mod holiday;
+mod load_balancing;
+mod mode;
+#[cfg(not(target_arch = "wasm32"))]
+mod network;
+mod overrides;
+mod patch;
+mod presets;
+pub mod protocol;
+mod quota;
+mod rate_limit;
+mod redact;
+mod response;
+mod retry;
+mod rooms;
+mod runtime;
+#[cfg(feature = "keyring")]
+mod secret_store;
+mod select;
+mod setpoint;
+mod shared;
+mod smoothing;
+mod sort;
+mod state;
+mod status_code;
+mod summary;
+#[cfg(feature = "chrono")]
+mod timestamps;
+mod token_manager;
+mod token_store;
+mod transport;
+mod units;
+mod window;
+
+pub use api_error::{ApiError, ApiErrorBody, ApiFault, ApiFaultDetail};
+pub use availability::{DeviceAvailability, ObservationGap, MAX_OBSERVATION_GAPS};
+pub use banner::BannerState;
+pub use boost::Boost;
+pub use builder::AllyApiBuilder;
+pub use capabilities::Capabilities;
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use commands::{BatchResults, Command, CommandRejected, OutOfRange, Unsupported};
+pub use config::{Config, ConfigError, LoggingConfig};
+use config::{base_url_from_env, credential_from_env};
+use correlation::new_correlation_id;
+pub use device_kind::DeviceKind;
+pub use device_status::DeviceStatus;
+pub use error::{AllyError, MAX_ERROR_BODY_CHARS};
+pub use events::{DeviceDiff, DeviceEvent, SnapshotDiff, StatusChange};
+pub use faults::FaultCode;
+pub use fleet::{AllyFleet, FleetDevice, FleetResults, ACCOUNT_TAG};
+pub use format::{
+    humanize_ago, humanize_battery, humanize_temperature, render_table, Column,
+};
+pub use frost::FROST_PROTECTION_TEMPERATURE;
+pub use heating::WorkState;
+pub use holiday::{Holiday, InvalidHoliday};
+pub use http;
+pub use load_balancing::LoadBalancing;
+pub use mode::Mode;
+#[cfg(not(target_arch = "wasm32"))]
+pub use network::{IpFamily, NetworkConfig};
+pub use overrides::{DeviceOverrides, OverrideError};
+pub use patch::{json_patch, snapshot, PatchOperation};
+pub use presets::{Preset, PresetTemperatures};
+use protocol::{DEVICES_PATH, TOKEN_PATH};
+pub use quota::{QuotaAction, QuotaDecision, QuotaExceeded, QuotaTracker};
+pub use rate_limit::RateLimit;
+pub use redact::redact_body;
+use redact::Secret;
+pub use response::{parse_retry_after, ResponseMeta};
+pub use retry::{Backoff, Jitter, RetryPolicy};
+pub use rooms::Room;
+use runtime::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "keyring")]
+pub use secret_store::KEYRING_SERVICE;
+pub use setpoint::{InvalidSetpoint, SetpointLimits};
+use shared::SharedState;
+pub use smoothing::{Smoothing, SmoothingMethod};
+use smoothing::{Smoother, SMOOTHED_CODES};
+pub use sort::SortKey;
+pub use state::ClientState;
+pub use status_code::StatusCode;
+pub use summary::HouseSummary;
+pub use token_manager::TokenManager;
+pub use token_store::{FileTokenStore, TokenStore};
+use token_store::key_fingerprint;
+pub use transport::{HttpTransport, ReqwestTransport};
+pub use units::TemperatureUnit;
+pub use window::{WindowState, WindowStatus};
+
+/// Default base URL of the Danfoss API
+const API_BASE_URL: &str = "https://api.danfoss.com";
 
 /// A struct representing a danfoss api token
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// The `Debug` output never contains the access token itself.
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Token {
     /// The access token that needs to be sent with every request to the API
     pub access_token: String,
     /// Type of the access token
     pub token_type: String,
-    /// Validity duration of the token in seconds.
-    pub expires_in: String,
+    /// Validity duration of the token in seconds. The API sends it either as
+    /// a number or as a numeric string.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub expires_in: u64,
+    /// When the token was received, set by the client
+    #[serde(default)]
+    pub issued_at: Option<SystemTime>,
+    /// Fingerprint of the API key the token was issued for, set by the
+    /// client. Stored tokens of other keys are not reused.
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+impl Token {
+    /// Validity duration of the token
+    pub fn expires_in(&self) -> Duration {
+        Duration::from_secs(self.expires_in)
+    }
+
+    /// When the token expires, if it is known when it was received
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.issued_at
+            .map(|issued_at| issued_at + self.expires_in())
+    }
+}
+
+/// Accept a number of seconds encoded as a JSON number or a numeric string
+fn deserialize_seconds<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Fraction(f64),
+        Text(String),
+    }
+    match Seconds::deserialize(deserializer)? {
+        Seconds::Number(seconds) => Ok(seconds),
+        Seconds::Fraction(seconds) => Ok(seconds.max(0.0) as u64),
+        Seconds::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Token")
+            .field("access_token", &Secret::new(self.access_token.clone()))
+            .field("token_type", &self.token_type)
+            .field("expires_in", &self.expires_in)
+            .field("issued_at", &self.issued_at)
+            .field("account", &self.account)
+            .finish()
+    }
 }
 
 /// A struct representing the response for the /devices/ endpoint
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DevicesResponse {
-    /// A list of all devices connected to your account
+    /// A list of all devices connected to your account. Entries that cannot
+    /// be read as a device are skipped with a warning.
+    #[serde(deserialize_with = "deserialize_devices")]
     pub result: Vec<Device>,
-    /// An identifier
+    /// Server time when the response was generated, in milliseconds since the UNIX epoch
+    #[serde(default)]
     pub t: i64,
 }
 
-// A struct implementing the [device schema](https://developer.danfoss.com/docs/ally/1/types/device)
+/// Deserialize a list of devices, skipping malformed entries instead of
+/// failing the whole list
+fn deserialize_devices<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Device>, D::Error> {
+    let entries = Vec::<Value>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match serde_json::from_value(entry) {
+            Ok(device) => Some(device),
+            Err(e) => {
+                warn!("Skipping device that could not be read. {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+impl DevicesResponse {
+    /// Server time when the response was generated
+    pub fn server_time(&self) -> SystemTime {
+        let millis = Duration::from_millis(self.t.unsigned_abs());
+        if self.t >= 0 {
+            UNIX_EPOCH + millis
+        } else {
+            UNIX_EPOCH - millis
+        }
+    }
+}
+
+/// A struct representing the response for the /devices/{device_id} endpoint
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceResponse {
+    /// The requested device
+    pub result: Device,
+    /// Server time when the response was generated, in milliseconds since the UNIX epoch
+    pub t: i64,
+}
+
+/// A struct representing the response for the /devices/{device_id}/status endpoint
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusResponse {
+    /// Status values of the requested device
+    pub result: Vec<Status>,
+    /// Server time when the response was generated, in milliseconds since the UNIX epoch
+    pub t: i64,
+}
+
+/// A struct implementing the [device schema](https://developer.danfoss.com/docs/ally/1/types/device)
+///
+/// Only `id` is required. Missing fields take their default value, and
+/// fields this crate does not know are kept in `extra`.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Device {
     /// Time when last seen online
+    #[serde(default)]
     pub active_time: i64,
     /// Time when the device was setup
+    #[serde(default)]
     pub create_time: i64,
     /// Unique identifier of the device
     pub id: String,
     /// User specified name of the device
+    #[serde(default)]
     pub name: String,
     /// Online status of the device
+    #[serde(default)]
     pub online: bool,
     /// Current settings for the device
+    #[serde(default)]
     pub status: Vec<Status>,
     /// Indicates whether this device is controlled by a gateway. True: yes, false: no
+    #[serde(default)]
     pub sub: bool,
     /// Time Zone
+    #[serde(default)]
     pub time_zone: String,
     /// Last update of device setting
+    #[serde(default)]
     pub update_time: i64,
     /// Type of device
+    #[serde(default)]
     pub device_type: String,
+    /// Fields of the device not known to this crate
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Device {
+    /// Raw value of the status with the given code, if the device reports it
+    pub fn status_value(&self, code: impl Into<StatusCode>) -> Option<&Value> {
+        let code = code.into();
+        self.status
+            .iter()
+            .find(|status| status.code == code)
+            .map(|status| &status.value)
+    }
+
+    /// Firmware version reported by the device (`sw_version`), if any
+    pub fn firmware_version(&self) -> Option<String> {
+        self.status_value(StatusCode::SwVersion)
+            .map(|value| match value {
+                Value::String(version) => version.clone(),
+                other => other.to_string(),
+            })
+    }
+
+    /// OTA upgrade status reported by the device (`upgrade_status`), if any
+    pub fn ota_status(&self) -> Option<OtaStatus> {
+        self.status_value(StatusCode::UpgradeStatus)
+            .map(OtaStatus::from_value)
+    }
+
+    /// Whether the device reports a pending firmware upgrade
+    pub fn needs_update(&self) -> bool {
+        matches!(self.ota_status(), Some(OtaStatus::Available))
+    }
+
+    /// Measured temperature (`va_temperature` or `temp_current`) in the given unit
+    pub fn current_temperature(&self, unit: TemperatureUnit) -> Option<f32> {
+        self.status_value(StatusCode::VaTemperature)
+            .or_else(|| self.status_value(StatusCode::TempCurrent))
+            .and_then(numeric_value)
+            .map(|raw| unit.decode_deci_celsius(raw))
+    }
+
+    /// Relative humidity in percent (`va_humidity`), reported by room sensors
+    pub fn humidity(&self) -> Option<f32> {
+        self.status_value(StatusCode::VaHumidity)
+            .and_then(numeric_value)
+            .map(|raw| raw / 10.0)
+    }
+
+    /// Battery level in percent (`battery_percentage`), if reported
+    pub fn battery(&self) -> Option<u8> {
+        self.status_value(StatusCode::BatteryPercentage)
+            .and_then(numeric_value)
+            .map(|level| level.clamp(0.0, 100.0).round() as u8)
+    }
+
+    /// Whether the battery level is below `threshold` percent. Devices
+    /// without a battery are never low.
+    pub fn is_battery_low(&self, threshold: u8) -> bool {
+        self.battery().is_some_and(|level| level < threshold)
+    }
+
+    /// Target temperature (`temp_set`) in the given unit
+    pub fn setpoint(&self, unit: TemperatureUnit) -> Option<f32> {
+        self.status_value(StatusCode::TempSet)
+            .and_then(numeric_value)
+            .map(|raw| unit.decode_deci_celsius(raw))
+    }
+
+    /// Whether the device is an Ally gateway
+    pub fn is_gateway(&self) -> bool {
+        self.kind() == DeviceKind::Gateway
+    }
+}
+
+/// Connectivity overview of a gateway and the devices controlled through it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayStatus {
+    /// Unique identifier of the gateway
+    pub id: String,
+    /// User specified name of the gateway
+    pub name: String,
+    /// Online status of the gateway
+    pub online: bool,
+    /// Time when the gateway was last seen online
+    pub active_time: i64,
+    /// Firmware version reported by the gateway, if any
+    pub firmware_version: Option<String>,
+    /// Number of devices controlled by a gateway
+    pub sub_devices: usize,
+    /// Number of devices controlled by a gateway that are currently offline
+    pub offline_sub_devices: usize,
+}
+
+impl GatewayStatus {
+    /// Whether the gateway appears to be stuck: it is offline itself, or it
+    /// is online while every device behind it is offline.
+    ///
+    /// The API does not expose a way to restart a gateway; power cycling it
+    /// is usually the fix.
+    pub fn is_unhealthy(&self) -> bool {
+        !self.online || (self.sub_devices > 0 && self.offline_sub_devices == self.sub_devices)
+    }
+}
+
+/// Read a status value that may be encoded as a number or a numeric string
+fn numeric_value(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|number| number as f32),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
 }
+
+fn bool_value(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::Number(number) => number.as_u64().map(|number| number != 0),
+        Value::String(text) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "on" => Some(true),
+            "false" | "0" | "off" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Values of a device setting
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Status {
     /// Status code
-    pub code: String,
+    pub code: StatusCode,
     /// Value of the status code
     pub value: Value,
 }
 
+impl Status {
+    /// Value as a number, also if it is encoded as a numeric string
+    ///
+    /// ```
+    /// use danfoss_ally_rs::{Status, StatusCode, TemperatureUnit};
+    /// use serde_json::json;
+    ///
+    /// let status = Status { code: StatusCode::TempSet, value: json!("215") };
+    /// assert_eq!(status.as_f64(), Some(215.0));
+    /// assert_eq!(status.as_temperature(TemperatureUnit::Celsius), Some(21.5));
+    /// ```
+    pub fn as_f64(&self) -> Option<f64> {
+        match &self.value {
+            Value::Number(number) => number.as_f64(),
+            Value::String(text) => text.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Value as a boolean, also if it is encoded as `0`/`1` or as a
+    /// `"true"`/`"false"` or `"on"`/`"off"` string
+    pub fn as_bool(&self) -> Option<bool> {
+        bool_value(&self.value)
+    }
+
+    /// Value as text, if it is a string
+    pub fn as_str(&self) -> Option<&str> {
+        self.value.as_str()
+    }
+
+    /// Value as a temperature in the given unit, decoded from tenths of a
+    /// degree Celsius
+    pub fn as_temperature(&self, unit: TemperatureUnit) -> Option<f32> {
+        numeric_value(&self.value).map(|raw| unit.decode_deci_celsius(raw))
+    }
+}
+
+/// Over-the-air upgrade state of a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtaStatus {
+    /// No upgrade pending
+    UpToDate,
+    /// A firmware upgrade is available but not yet installed
+    Available,
+    /// A firmware upgrade is being installed
+    Upgrading,
+    /// The last firmware upgrade finished successfully
+    Succeeded,
+    /// The last firmware upgrade failed
+    Failed,
+    /// A value this library does not know how to interpret
+    Unknown(String),
+}
+
+impl OtaStatus {
+    /// Interpret the raw `upgrade_status` value. Both the numeric (0-4) and
+    /// the textual encoding are understood.
+    pub fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Number(number) => match number.as_u64() {
+                Some(0) => OtaStatus::UpToDate,
+                Some(1) => OtaStatus::Available,
+                Some(2) => OtaStatus::Upgrading,
+                Some(3) => OtaStatus::Succeeded,
+                Some(4) => OtaStatus::Failed,
+                _ => OtaStatus::Unknown(number.to_string()),
+            },
+            Value::String(text) => match text.to_lowercase().as_str() {
+                "idle" | "none" | "latest" | "up_to_date" => OtaStatus::UpToDate,
+                "available" | "ready" | "pending" => OtaStatus::Available,
+                "upgrading" | "in_progress" => OtaStatus::Upgrading,
+                "success" | "succeeded" => OtaStatus::Succeeded,
+                "failed" | "failure" => OtaStatus::Failed,
+                _ => OtaStatus::Unknown(text.clone()),
+            },
+            other => OtaStatus::Unknown(other.to_string()),
+        }
+    }
+}
+
+/// Local key/value attributes attached to a device
+pub type DeviceTags = BTreeMap<String, String>;
+
 /// Struct that holds all information to interact with the Danfoss ally api
 /// 
 /// You will need credentials for the API that are exposed through environment
@@ -69,149 +511,1002 @@ pub struct Status {
 /// # Examples
 /// 
 /// Simple example
-/// ```
+/// ```no_run
 /// use danfoss_ally_rs::AllyApi;
 ///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
 /// let mut danfoss_api: AllyApi = AllyApi::new();
-/// danfoss_api.get_token();
-/// danfoss_api.get_devices();
-///
+/// danfoss_api.get_devices().await?;
+/// # Ok(())
+/// # }
 /// ```
 /// 
-/// More comprehensive example that fetches the device status every 30 seconds
-/// and handles refreshing the token
+/// More comprehensive example that fetches the device status every 30 seconds.
+/// The token is refreshed automatically shortly before it expires
 /// 
-/// ```
-/// use danfoss_ally_rs::AllyApi;
-/// use chrono::Utc;
+/// ```no_run
+/// use danfoss_ally_rs::{AllyApi, StatusCode};
 /// use log::*;
-/// use std::env;
 /// use std::thread::sleep;
-/// use std::time::{Duration, Instant, SystemTime};
-/// 
-
+/// use std::time::Duration;
+///
 /// #[cfg(not(target_arch = "wasm32"))]
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
 ///     env_logger::init();
 ///     info! {"Starting up"};
-///     let danfoss_api = AllyApi::new();
+///     let mut danfoss_api = AllyApi::new();
 ///     loop {
-///         sleep(Duration::new(30, 0));
-///         if self.danfoss_api.time_since_token_renewal.elapsed().as_secs()
-///             >= self.danfoss_api.token.expires_in.parse::<u64>()?
-///         {
-///             self.danfoss_api.get_token()
-///                 .await
-///                 .unwrap_or_else(|e| error!("Could not fetch token. {:?}", e));
-///             self.danfoss_api.time_since_token_renewal = Instant::now();
-///         }
-///         self.danfoss_api.get_devices()
+///         danfoss_api.get_devices()
 ///             .await
 ///             .unwrap_or_else(|e| error!("Could not get devices. {:?}", e));
-///         self.danfoss_api.time_since_update = Instant::now();
-///         for device in &self.devices {
+///         for device in &danfoss_api.devices {
 ///             for status in &device.status {
-///                 if status.code == "va_temperature" || status.code == "temp_current" {
+///                 if matches!(status.code, StatusCode::VaTemperature | StatusCode::TempCurrent) {
 ///                     debug!("{}: {}", device.name, status.value);
 ///                 }
 ///             }
 ///         }
+///         sleep(Duration::new(30, 0));
 ///     }
-///     Ok(())
 /// }
 ///
 /// #[cfg(target_arch = "wasm32")]
 /// fn main() {}
-/// 
 /// ```
-#[derive(Debug)]
+///
+/// # Sharing between tasks
+///
+/// Methods that only send requests take `&self`, and the client is cheap to
+/// clone. Clones share the credentials, the access token, the HTTP transport,
+/// the quota, rate limit and circuit breaker state as well as boosts and frost
+/// protection. Each clone keeps its own settings and device cache, which only
+/// [`AllyApi::get_devices`] and the other cache updates change through `&mut self`.
+/// [`AllyApi::fetch_devices`] returns the devices without a cache.
+///
+/// ```no_run
+/// use danfoss_ally_rs::AllyApi;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let api = AllyApi::new();
+/// let tasks: Vec<_> = ["kitchen-id", "bedroom-id"]
+///     .into_iter()
+///     .map(|device_id| {
+///         let api = api.clone();
+///         tokio::spawn(async move { api.set_temperature(device_id, 21.0).await })
+///     })
+///     .collect();
+/// for task in tasks {
+///     task.await??;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
 pub struct AllyApi {
     /// List of devices connected to the account
     pub devices: Vec<Device>,
-    /// Access token for the API
-    pub token: Token,
     /// Time since the last API call. The free API in general has throttling enabled which apply across the API. 
     /// Throttling kicking in can be identified by receiving status code 429 - too many request. 
     /// E.g. the /token endpoint has a maximum of 5 calls per second.
     pub time_since_update: Instant,
-    /// Time since the last access token was fetched
-    pub time_since_token_renewal: Instant,
     /// How often the run function should poll data. Default: Every 30 seconds
     pub polling_interval: Duration,
-    api_key: String,
-    api_secret: String,
-    reqwest_client: reqwest::Client,
+    /// How long before expiry the token is renewed. Tokens valid for less
+    /// than twice the margin are renewed after half their lifetime instead.
+    /// Default: 60 seconds
+    pub token_refresh_margin: Duration,
+    /// Minimum time between two token requests. Default: 1 second
+    pub min_token_interval: Duration,
+    /// Server time of the last devices response
+    pub server_time: Option<SystemTime>,
+    /// Difference between the server clock and the local clock in milliseconds,
+    /// measured against the midpoint of the last devices request. Positive
+    /// values mean the server clock is ahead.
+    pub clock_skew_ms: Option<i64>,
+    /// Availability history per device id, updated on every devices poll
+    pub availability: HashMap<String, DeviceAvailability>,
+    /// Local tags per device id, e.g. `floor = "1"` or `battery = "AA"`.
+    /// The Danfoss cloud only stores a name, so tags never leave this client.
+    pub tags: HashMap<String, DeviceTags>,
+    /// Local friendly names mapped to device ids. Aliases stay stable when a
+    /// device is renamed in the Danfoss app.
+    pub aliases: HashMap<String, String>,
+    /// Per-device settings keyed by device id or alias, see [`AllyApi::overrides_for`]
+    pub device_overrides: HashMap<String, DeviceOverrides>,
+    /// Rooms and the devices they contain, see [`AllyApi::room_of`]
+    pub rooms: Vec<Room>,
+    /// House-wide temperatures of the comfort presets
+    pub presets: PresetTemperatures,
+    /// Preset temperatures per room, taking precedence over `presets`
+    pub room_presets: HashMap<String, PresetTemperatures>,
+    /// Unit used when logging and rendering temperatures, and that setpoints
+    /// can be written in with [`AllyApi::set_temperature_in`]. Default: Celsius
+    pub temperature_unit: TemperatureUnit,
+    /// Order in which devices are kept after each poll. Default: by name
+    pub sort_order: SortKey,
+    /// Record a JSON Patch of the device state on every poll, see [`AllyApi::take_patch`].
+    /// Default: false
+    pub record_patches: bool,
+    /// Smoothing of noisy temperature and humidity readings. Default: None
+    pub smoothing: Option<Smoothing>,
+    /// Header that carries the correlation id of each request, e.g.
+    /// `X-Request-ID`. Default: None, the id only appears in logs and errors
+    pub correlation_header: Option<String>,
+    /// How many requests [`AllyApi::send_commands_batch`] keeps in flight. Default: 4
+    pub max_concurrent_requests: usize,
+    /// Accepted setpoints per device kind. Kinds without an entry accept
+    /// 5-35 °C in steps of 0.5 °C
+    pub setpoint_limits: HashMap<DeviceKind, SetpointLimits>,
+    /// Where the access token is kept between process restarts, e.g.
+    /// [`FileTokenStore::in_cache_dir`]. A stored token that is still valid
+    /// is used instead of requesting a new one. Default: None
+    pub token_store: Option<Arc<dyn TokenStore>>,
+    /// Base URL of the API, without a trailing slash. Default:
+    /// `DANFOSS_BASE_URL` with [`AllyApi::try_new`] and [`AllyApi::builder`],
+    /// otherwise `https://api.danfoss.com`
+    pub base_url: String,
+    /// Retry requests that failed for transient reasons, see
+    /// [`AllyError::is_transient`], including each request of
+    /// [`AllyApi::send_commands_batch`]. Default: None, requests are not
+    /// retried
+    pub retry: Option<RetryPolicy>,
+    /// Limit for API requests; requests beyond it are delayed. Default: 5 per second
+    pub rate_limit: Option<RateLimit>,
+    /// Limit for token requests; requests beyond it are delayed. Default: 5 per second
+    pub token_rate_limit: Option<RateLimit>,
+    transport: Arc<dyn HttpTransport>,
+    shared: Arc<Mutex<SharedState>>,
+    events: Vec<DeviceEvent>,
+    patch: Vec<PatchOperation>,
+    smoothers: HashMap<(String, StatusCode), Smoother>,
+    tokens: TokenManager,
+}
+
+// Clients are shared between tasks by cloning them
+const _: fn() = || {
+    fn assert_shareable<T: Clone + Send + Sync>() {}
+    assert_shareable::<AllyApi>();
+};
+
+/// A client without credentials that does not read the environment. Token
+/// requests fail until credentials are set with
+/// [`AllyApi::rotate_credentials`]; use [`AllyApi::try_new`] or
+/// [`AllyApi::builder`] to configure one instead.
+impl Default for AllyApi {
+    fn default() -> Self {
+        Self::with_credentials("", "")
+    }
 }
 
 /// API client implementation for Danfoss Ally
 /// 
 ///
 impl AllyApi {
-    /// Create new danfoss ally client
+    /// Create new danfoss ally client with the credentials from the
+    /// `DANFOSS_API_KEY` and `DANFOSS_API_SECRET` environment variables.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either variable is not set, see [`AllyApi::try_new`].
     pub fn new() -> Self {
-        let api_key = env::var("DANFOSS_API_KEY").expect("No Danfoss API key provided. Please set DANFOSS_API_KEY environment variable.");
+        Self::try_new().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create new danfoss ally client with the credentials from the
+    /// `DANFOSS_API_KEY` and `DANFOSS_API_SECRET` environment variables,
+    /// failing if either is not set. With the `keyring` feature, variables
+    /// that are not set are looked up in the platform secret store.
+    ///
+    /// `DANFOSS_BASE_URL` replaces the default base URL if set, e.g. to
+    /// route requests through a gateway or to a mock server.
+    pub fn try_new() -> Result<Self, ConfigError> {
+        let api_key = credential_from_env("DANFOSS_API_KEY")?;
+        let api_secret = credential_from_env("DANFOSS_API_SECRET")?;
+        let mut api = Self::with_credentials(api_key, api_secret);
+        if let Some(base_url) = base_url_from_env()? {
+            api.base_url = base_url;
+        }
+        Ok(api)
+    }
 
-        let api_secret = env::var("DANFOSS_API_SECRET").expect("No Danfoss API secret provided.Please set DANFOSS_API_SECRET environment variable.");
+    /// Create new danfoss ally client with the given credentials
+    pub fn with_credentials(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), api_key, api_secret)
+    }
+
+    /// Create new danfoss ally client with the given credentials that sends
+    /// its requests through `client`, e.g. one with custom TLS roots, a proxy
+    /// or tuned connection pooling. [`AllyApi::set_network_config`] replaces
+    /// the client.
+    pub fn with_client(
+        client: reqwest::Client,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> Self {
+        Self::with_transport(Arc::new(ReqwestTransport::new(client)), api_key, api_secret)
+    }
 
+    /// Create new danfoss ally client with the given credentials that sends
+    /// its requests through a custom [`HttpTransport`]. The transport can be
+    /// shared by several clients.
+    pub fn with_transport(
+        transport: Arc<dyn HttpTransport>,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> Self {
         Self {
             devices: vec![],
-            token: Token {
-                access_token: String::new(),
-                token_type: String::new(),
-                expires_in: "0".to_string(),
-            },
-            api_key,
-            api_secret,
             time_since_update: Instant::now(),
-            time_since_token_renewal: Instant::now(),
-            reqwest_client: reqwest::Client::new(),
+            transport,
+            shared: Arc::new(Mutex::new(SharedState::new(
+                api_key.into(),
+                api_secret.into(),
+            ))),
             polling_interval: Duration::new(30,0),
+            token_refresh_margin: Duration::from_secs(60),
+            min_token_interval: Duration::from_secs(1),
+            server_time: None,
+            clock_skew_ms: None,
+            availability: HashMap::new(),
+            tags: HashMap::new(),
+            aliases: HashMap::new(),
+            device_overrides: HashMap::new(),
+            rooms: vec![],
+            presets: PresetTemperatures::default(),
+            room_presets: HashMap::new(),
+            temperature_unit: TemperatureUnit::Celsius,
+            sort_order: SortKey::Name,
+            record_patches: false,
+            smoothing: None,
+            correlation_header: None,
+            max_concurrent_requests: 4,
+            setpoint_limits: HashMap::new(),
+            token_store: None,
+            base_url: API_BASE_URL.to_string(),
+            retry: None,
+            rate_limit: Some(RateLimit::per_second(5)),
+            token_rate_limit: Some(RateLimit::per_second(5)),
+            events: vec![],
+            patch: vec![],
+            smoothers: HashMap::new(),
+            tokens: TokenManager::new(),
         }
     }
-    /// Fetch access token with the provided credentials
-    pub async fn get_token(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let basic_auth: String = base64::encode(format!("{}:{}", self.api_key, self.api_secret));
-        let authorization_header: String = format!("Basic {}", basic_auth);
-
-        let params = [("grant_type", "client_credentials")];
-        let res = self
-            .reqwest_client
-            .post("https://api.danfoss.com/oauth2/token")
-            .header("content-type", "application/x-www-form-urlencoded")
-            .header("accept", "application/json")
-            .header("authorization", authorization_header)
-            .form(&params)
-            .send()
+    /// Fetch access token with the provided credentials.
+    ///
+    /// This always calls the token endpoint. Prefer [`AllyApi::ensure_token`],
+    /// which only does so when the current token is about to expire.
+    pub async fn get_token(&self) -> Result<(), AllyError> {
+        let correlation_id = self.start_operation();
+        let _refresh = self.tokens.lock_refresh().await;
+        self.request_token(&correlation_id)
+            .await
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    /// Fetch a token with the current credentials and use it. Callers hold
+    /// the refresh lock, so the credentials cannot be rotated meanwhile.
+    async fn request_token(&self, correlation_id: &str) -> Result<(), AllyError> {
+        let (api_key, api_secret) = {
+            let shared = self.shared();
+            (
+                shared.api_key.expose().to_string(),
+                shared.api_secret.expose().to_string(),
+            )
+        };
+        let token = self
+            .request_token_for(correlation_id, &api_key, &api_secret)
             .await?;
-        self.token = serde_json::from_str(res.text().await?.as_str())?;
+        self.use_token(token);
         Ok(())
     }
+
+    /// Fetch a token for the given credentials, retrying transient failures
+    async fn request_token_for(
+        &self,
+        correlation_id: &str,
+        api_key: &str,
+        api_secret: &str,
+    ) -> Result<Token, AllyError> {
+        let mut attempt = 0;
+        let mut previous = Duration::ZERO;
+        loop {
+            self.check_circuit()?;
+            let result = self
+                .request_token_once(correlation_id, api_key, api_secret)
+                .await;
+            self.record_outcome(&result);
+            let error = match result {
+                Err(e) => e,
+                result => return result,
+            };
+            let Some(delay) = self.retry_delay(attempt, previous, &error) else {
+                return Err(error);
+            };
+            warn!(
+                "[{}] Token request failed, retrying in {:?}. {}",
+                correlation_id, delay, error
+            );
+            runtime::sleep(delay).await;
+            attempt += 1;
+            previous = delay;
+        }
+    }
+
+    async fn request_token_once(
+        &self,
+        correlation_id: &str,
+        api_key: &str,
+        api_secret: &str,
+    ) -> Result<Token, AllyError> {
+        debug!("[{}] Requesting access token", correlation_id);
+        self.count_request().await?;
+        self.wait_for_rate_limit(true).await;
+        self.tokens.mark_requested();
+        let request = protocol::token_request(&self.base_url, api_key, api_secret)?;
+        let request = self.with_correlation_header(request, correlation_id)?;
+        let res = self.transport.send(request).await?;
+        self.record_response(TOKEN_PATH, &res);
+        trace!("[{}] Token response: {}", correlation_id, redact_body(res.body()));
+        let mut token: Token = protocol::parse_response(TOKEN_PATH, &res)?;
+        token.issued_at = Some(SystemTime::now());
+        token.account = Some(key_fingerprint(api_key));
+        Ok(token)
+    }
+
+    /// Replace the held token and persist it in the token store, if any
+    fn use_token(&self, token: Token) {
+        let lifetime = token.expires_in();
+        self.tokens.set(token, lifetime);
+        self.store_token();
+    }
     
-    /// Get all devices and their status from the API
-    pub async fn get_devices(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let res = self
-            .reqwest_client
-            .get("https://api.danfoss.com/ally/devices")
-            .header("accept", "application/json")
-            .header(
-                "authorization",
-                format!("Bearer {}", self.token.access_token),
-            )
-            .send()
-            .await?;
-        let devices: DevicesResponse = serde_json::from_str(res.text().await?.as_str())?;
+    /// Switch to new API credentials without interrupting the client.
+    ///
+    /// A token is fetched with the new credentials right away. Only if that
+    /// succeeds are the old credentials and token retired; on failure the
+    /// client keeps working with the previous ones and the error is returned.
+    /// Requests of clones keep using the previous token until the switch.
+    pub async fn rotate_credentials(
+        &self,
+        api_key: &str,
+        api_secret: &str,
+    ) -> Result<(), AllyError> {
+        let correlation_id = self.start_operation();
+        let _refresh = self.tokens.lock_refresh().await;
+        match self
+            .request_token_for(&correlation_id, api_key, api_secret)
+            .await
+        {
+            Ok(token) => {
+                {
+                    let mut shared = self.shared();
+                    shared.api_key = Secret::new(api_key.to_string());
+                    shared.api_secret = Secret::new(api_secret.to_string());
+                }
+                self.use_token(token);
+                info!("Switched to new API credentials");
+                Ok(())
+            }
+            Err(e) => {
+                warn!("New API credentials were rejected, keeping the previous ones");
+                Err(AllyError::correlated(&correlation_id, e))
+            }
+        }
+    }
+
+    /// Rebuild the HTTP client with the given name resolution and IP family
+    /// settings. A client passed to [`AllyApi::with_client`] or a custom
+    /// transport is replaced.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_network_config(&mut self, config: &NetworkConfig) -> Result<(), reqwest::Error> {
+        let client = config.apply(reqwest::Client::builder()).build()?;
+        self.transport = Arc::new(ReqwestTransport::new(client));
+        Ok(())
+    }
+
+    /// Copy of the current access token
+    pub fn token(&self) -> Token {
+        self.tokens.token()
+    }
+
+    /// Manager holding the access token. A clone of it can be handed to
+    /// other tasks to watch the token while the client is in use.
+    pub fn token_manager(&self) -> &TokenManager {
+        &self.tokens
+    }
+
+    /// Correlation id of the most recent operation
+    pub fn last_correlation_id(&self) -> Option<String> {
+        self.shared().last_correlation_id.clone()
+    }
+
+    /// Status, request id and rate limit headers of the most recent response
+    pub fn last_response(&self) -> Option<ResponseMeta> {
+        self.shared().last_response.clone()
+    }
+
+    /// Whether the token is missing or expires within `token_refresh_margin`.
+    ///
+    /// Expiry is checked against both the monotonic and the wall clock, so
+    /// a token is not trusted after the system was suspended past its lifetime.
+    pub fn token_needs_refresh(&self) -> bool {
+        self.tokens.needs_refresh(self.token_refresh_margin)
+    }
+
+    /// Wall clock time when the current token expires, `None` without a token
+    pub fn token_expires_at(&self) -> Option<SystemTime> {
+        self.tokens.expires_at()
+    }
+
+    /// Time left until the current token expires, `None` without a token.
+    ///
+    /// The earlier of the monotonic and the wall clock deadline counts; an
+    /// expired token has zero time left.
+    pub fn token_remaining(&self) -> Option<Duration> {
+        self.tokens.remaining()
+    }
+
+    /// Whether a token is held and has not expired yet.
+    ///
+    /// Unlike [`AllyApi::token_needs_refresh`] this ignores
+    /// `token_refresh_margin`.
+    pub fn is_token_valid(&self) -> bool {
+        self.tokens.is_valid()
+    }
+
+    /// Fetch a new access token if the current one is missing or about to
+    /// expire. Token requests are spaced at least `min_token_interval` apart.
+    ///
+    /// When clones of a client run in several tasks, the first task
+    /// refreshes the token and every task after it finds a valid token, so
+    /// an expired token leads to exactly one token request:
+    ///
+    /// ```no_run
+    /// use danfoss_ally_rs::AllyApi;
+    ///
+    /// # async fn run() {
+    /// let api = AllyApi::new();
+    /// let tasks: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let api = api.clone();
+    ///         tokio::spawn(async move { api.ensure_token().await.is_ok() })
+    ///     })
+    ///     .collect();
+    /// # }
+    /// ```
+    ///
+    /// Every API call does this by itself, so calling it is only needed to
+    /// fetch a token ahead of time.
+    pub async fn ensure_token(&self) -> Result<(), AllyError> {
+        let correlation_id = self.start_operation();
+        self.refresh_token_if_needed(&correlation_id)
+            .await
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    async fn refresh_token_if_needed(&self, correlation_id: &str) -> Result<(), AllyError> {
+        if self.tokens.is_empty() {
+            self.load_stored_token();
+        }
+        if !self.token_needs_refresh() {
+            return Ok(());
+        }
+        let _refresh = self.tokens.lock_refresh().await;
+        // A client sharing the token manager may have refreshed it meanwhile
+        if !self.token_needs_refresh() {
+            return Ok(());
+        }
+        self.refresh_token(correlation_id).await
+    }
+
+    /// Replace a token the API rejected, unless a client sharing the token
+    /// manager already did so
+    async fn replace_rejected_token(
+        &self,
+        rejected: &str,
+        correlation_id: &str,
+    ) -> Result<(), AllyError> {
+        let _refresh = self.tokens.lock_refresh().await;
+        if self.tokens.access_token() != rejected {
+            return Ok(());
+        }
+        self.refresh_token(correlation_id).await
+    }
+
+    /// Fetch a new token, spaced at least `min_token_interval` after the
+    /// previous one
+    async fn refresh_token(&self, correlation_id: &str) -> Result<(), AllyError> {
+        if let Some(since_last) = self.tokens.since_last_request() {
+            if since_last < self.min_token_interval {
+                runtime::sleep(self.min_token_interval - since_last).await;
+            }
+        }
+        self.request_token(correlation_id).await
+    }
+
+    /// Get all devices and their status from the API.
+    ///
+    /// An access token is fetched first if none is held yet or the current
+    /// one is about to expire, and boosts that have ended are reverted, see
+    /// [`AllyApi::boost`]. The devices are stored in [`AllyApi::devices`];
+    /// use [`AllyApi::fetch_devices`] to get them returned instead.
+    pub async fn get_devices(&mut self) -> Result<(), AllyError> {
+        self.revert_expired_boosts().await;
+        let correlation_id = self.start_operation();
+        self.request_devices(&correlation_id)
+            .await
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    /// Get all devices and their status from the API and return them,
+    /// ordered by `sort_order`.
+    ///
+    /// Unlike [`AllyApi::get_devices`] this takes `&self` and leaves the
+    /// cached [`AllyApi::devices`] untouched. Readings are returned as
+    /// received; smoothing, change events and availability tracking only
+    /// apply to the cache.
+    pub async fn fetch_devices(&self) -> Result<Vec<Device>, AllyError> {
+        let correlation_id = self.start_operation();
+        let response: Result<DevicesResponse, _> =
+            self.get_json(DEVICES_PATH, &correlation_id).await;
+        let mut devices = response
+            .map(|response| response.result)
+            .map_err(|e| AllyError::correlated(&correlation_id, e))?;
+        self.sort(&mut devices, self.sort_order);
+        Ok(devices)
+    }
+
+    async fn request_devices(&mut self, correlation_id: &str) -> Result<(), AllyError> {
+        self.refresh_token_if_needed(correlation_id).await?;
+        let request_started = SystemTime::now();
+        let devices: DevicesResponse = self.get_json(DEVICES_PATH, correlation_id).await?;
+        self.record_server_time(&devices, request_started);
+        self.smooth_readings(&devices.result);
+        self.detect_changes(&devices.result);
+        self.track_availability(&devices.result);
+        if self.record_patches {
+            let mut operations = json_patch(&snapshot(&self.devices), &snapshot(&devices.result));
+            self.patch.append(&mut operations);
+        }
         self.devices = devices.result;
+        self.sort_devices(self.sort_order);
+        self.unresolved_aliases();
         self.time_since_update = Instant::now();
-        if log_enabled!(Level::Debug) {
+        if debug_enabled!() {
             for device in &self.devices {
-                for status in &device.status {
-                    if status.code == "va_temperature" || status.code == "temp_current" {
-                        debug!("{}: {}", device.name, status.value);
-                    }
+                if let Some(temperature) = device.current_temperature(self.temperature_unit) {
+                    debug!(
+                        "[{}] {}: {:.1}{}",
+                        correlation_id,
+                        device.name,
+                        temperature,
+                        self.temperature_unit.symbol()
+                    );
                 }
             }
         }
         Ok(())
     }
+
+    /// Like [`AllyApi::get_devices`], but gives up once `budget` is spent.
+    ///
+    /// The budget covers token acquisition, quota delays and the request
+    /// itself. On timeout [`AllyError::Timeout`] is returned and the cached
+    /// devices are left untouched.
+    pub async fn get_devices_within(&mut self, budget: Duration) -> Result<(), AllyError> {
+        runtime::timeout(budget, self.get_devices())
+            .await
+            .ok_or(AllyError::Timeout(budget))?
+    }
+
+    /// Get a single device and its status from the API.
+    ///
+    /// This is cheaper than [`AllyApi::get_devices`] when only one device is
+    /// of interest. The cached [`AllyApi::devices`] are left untouched.
+    pub async fn get_device(&self, id: &str) -> Result<Device, AllyError> {
+        let correlation_id = self.start_operation();
+        let response: Result<DeviceResponse, _> = match protocol::device_path(id) {
+            Ok(path) => self.get_json(&path, &correlation_id).await,
+            Err(e) => Err(e.into()),
+        };
+        response
+            .map(|response| response.result)
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    /// Get only the status values of a single device from the API.
+    ///
+    /// The cached [`AllyApi::devices`] are left untouched.
+    pub async fn get_device_status(&self, id: &str) -> Result<Vec<Status>, AllyError> {
+        let correlation_id = self.start_operation();
+        let response: Result<StatusResponse, _> = match protocol::device_status_path(id) {
+            Ok(path) => self.get_json(&path, &correlation_id).await,
+            Err(e) => Err(e.into()),
+        };
+        response
+            .map(|response| response.result)
+            .map_err(|e| AllyError::correlated(&correlation_id, e))
+    }
+
+    /// Wait for a newly paired device to show up.
+    ///
+    /// Pairing itself has to be started from the Danfoss Ally app, since the
+    /// API offers no endpoint for it. This polls the device list every
+    /// `polling_interval` and returns the first device that was not known
+    /// when the call started, or `None` if `timeout` elapses first.
+    pub async fn wait_for_new_device(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<Device>, AllyError> {
+        let known_ids: Vec<String> = self.devices.iter().map(|device| device.id.clone()).collect();
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            runtime::sleep(self.polling_interval.min(timeout.saturating_sub(started.elapsed()))).await;
+            self.get_devices().await?;
+            if let Some(device) = self.devices.iter().find(|device| !known_ids.contains(&device.id)) {
+                info!("New device {} ({}) found", device.name, device.id);
+                return Ok(Some(device.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// How old the last devices response is, measured on the server clock
+    pub fn data_age(&self) -> Option<Duration> {
+        let server_time = self.server_time?;
+        let skew = Duration::from_millis(self.clock_skew_ms?.unsigned_abs());
+        let now_on_server = if self.clock_skew_ms? >= 0 {
+            SystemTime::now() + skew
+        } else {
+            SystemTime::now() - skew
+        };
+        Some(now_on_server.duration_since(server_time).unwrap_or_default())
+    }
+
+    /// Devices that report an available firmware upgrade
+    pub fn devices_needing_update(&self) -> Vec<&Device> {
+        self.devices.iter().filter(|device| device.needs_update()).collect()
+    }
+
+    /// All gateways connected to the account
+    pub fn gateways(&self) -> Vec<&Device> {
+        self.devices.iter().filter(|device| device.is_gateway()).collect()
+    }
+
+    /// Devices controlled through a gateway.
+    ///
+    /// The API does not tell which gateway a device is paired with. Devices
+    /// with a `gateway` tag naming the gateway's id or alias are attributed
+    /// to it; untagged gateway-controlled devices are attributed to the
+    /// gateway only if it is the sole gateway of the account.
+    pub fn sub_devices(&self, gateway_id: &str) -> Vec<&Device> {
+        let sole_gateway = self.gateways().len() == 1;
+        self.devices
+            .iter()
+            .filter(|device| device.sub)
+            .filter(|device| match self.tag(&device.id, "gateway") {
+                Some(tag) => self.aliases.get(tag).map_or(tag, String::as_str) == gateway_id,
+                None => sole_gateway,
+            })
+            .collect()
+    }
+
+    /// Connectivity status of every gateway, with its devices attributed as
+    /// described in [`AllyApi::sub_devices`]
+    pub fn gateway_status(&self) -> Vec<GatewayStatus> {
+        self.gateways()
+            .into_iter()
+            .map(|gateway| {
+                let sub_devices = self.sub_devices(&gateway.id);
+                GatewayStatus {
+                    id: gateway.id.clone(),
+                    name: gateway.name.clone(),
+                    online: gateway.online,
+                    active_time: gateway.active_time,
+                    firmware_version: gateway.firmware_version(),
+                    sub_devices: sub_devices.len(),
+                    offline_sub_devices: sub_devices.iter().filter(|device| !device.online).count(),
+                }
+            })
+            .collect()
+    }
+
+    /// Attach a local tag to a device, replacing any previous value for `key`
+    pub fn set_tag(&mut self, device_id: &str, key: &str, value: &str) {
+        self.tags
+            .entry(device_id.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Remove a local tag from a device, returning its value
+    pub fn remove_tag(&mut self, device_id: &str, key: &str) -> Option<String> {
+        self.tags.get_mut(device_id)?.remove(key)
+    }
+
+    /// Value of a local tag of a device
+    pub fn tag(&self, device_id: &str, key: &str) -> Option<&str> {
+        self.tags.get(device_id)?.get(key).map(String::as_str)
+    }
+
+    /// Devices carrying the tag `key` with the given value
+    pub fn devices_with_tag(&self, key: &str, value: &str) -> Vec<&Device> {
+        self.devices
+            .iter()
+            .filter(|device| self.tag(&device.id, key) == Some(value))
+            .collect()
+    }
+
+    /// Register a friendly alias for a device id
+    pub fn set_alias(&mut self, alias: &str, device_id: &str) {
+        self.aliases.insert(alias.to_string(), device_id.to_string());
+    }
+
+    /// Look up a device by alias, id or name, in that order
+    pub fn resolve_device(&self, name: &str) -> Option<&Device> {
+        let id = self.aliases.get(name).map(String::as_str).unwrap_or(name);
+        self.devices
+            .iter()
+            .find(|device| device.id == id)
+            .or_else(|| self.devices.iter().find(|device| device.name == name))
+    }
+
+    /// Aliases pointing at device ids that are not part of the account
+    /// anymore. A warning is logged for each of them.
+    pub fn unresolved_aliases(&self) -> Vec<&str> {
+        let mut unresolved: Vec<&str> = self
+            .aliases
+            .iter()
+            .filter(|(_, id)| !self.devices.iter().any(|device| &device.id == *id))
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        unresolved.sort_unstable();
+        for alias in &unresolved {
+            warn!("Alias {} does not resolve to any device", alias);
+        }
+        unresolved
+    }
+
+    /// Take all events queued since the last call
+    pub fn take_events(&mut self) -> Vec<DeviceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Take the JSON Patch recorded since the last call.
+    ///
+    /// Applying it to the [`snapshot`] taken at the previous call (an empty
+    /// object before the first poll) yields the snapshot of the current
+    /// devices. Only recorded while `record_patches` is enabled.
+    pub fn take_patch(&mut self) -> Vec<PatchOperation> {
+        std::mem::take(&mut self.patch)
+    }
+
+    /// Send an authorized GET request to an API path such as `/ally/devices`
+    /// and deserialize the JSON response
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        correlation_id: &str,
+    ) -> Result<T, AllyError> {
+        self.send_json(http::Method::GET, path, None, correlation_id)
+            .await
+    }
+
+    /// Send an authorized POST request with a JSON body to an API path and
+    /// deserialize the JSON response
+    async fn post_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &Value,
+        correlation_id: &str,
+    ) -> Result<T, AllyError> {
+        self.send_json(http::Method::POST, path, Some(body), correlation_id)
+            .await
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: http::Method,
+        path: &str,
+        body: Option<&Value>,
+        correlation_id: &str,
+    ) -> Result<T, AllyError> {
+        self.refresh_token_if_needed(correlation_id).await?;
+        let mut attempt = 0;
+        let mut previous = Duration::ZERO;
+        loop {
+            self.check_circuit()?;
+            let result = self
+                .send_json_once(method.clone(), path, body, correlation_id)
+                .await;
+            self.record_outcome(&result);
+            let error = match result {
+                Err(e) => e,
+                result => return result,
+            };
+            let Some(delay) = self.retry_delay(attempt, previous, &error) else {
+                return Err(error);
+            };
+            warn!(
+                "[{}] {} {} failed, retrying in {:?}. {}",
+                correlation_id, method, path, delay, error
+            );
+            runtime::sleep(delay).await;
+            attempt += 1;
+            previous = delay;
+        }
+    }
+
+    async fn send_json_once<T: DeserializeOwned>(
+        &self,
+        method: http::Method,
+        path: &str,
+        body: Option<&Value>,
+        correlation_id: &str,
+    ) -> Result<T, AllyError> {
+        debug!("[{}] {} {}", correlation_id, method, path);
+        self.count_request().await?;
+        self.wait_for_rate_limit(false).await;
+        let sent_token = self.tokens.access_token();
+        let request = self.authorized_request(method.clone(), path, body, correlation_id)?;
+        let mut res = self.transport.send(request).await?;
+        if res.status() == http::StatusCode::UNAUTHORIZED {
+            debug!(
+                "[{}] Token rejected, fetching a new one and retrying",
+                correlation_id
+            );
+            self.record_response(path, &res);
+            self.replace_rejected_token(&sent_token, correlation_id)
+                .await?;
+            self.count_request().await?;
+            self.wait_for_rate_limit(false).await;
+            let request = self.authorized_request(method, path, body, correlation_id)?;
+            res = self.transport.send(request).await?;
+        }
+        self.record_response(path, &res);
+        trace!("[{}] Response of {}: {}", correlation_id, path, redact_body(res.body()));
+        protocol::parse_response(path, &res)
+    }
+
+    /// Build a request to an API path carrying the access token, the
+    /// correlation header and an optional JSON body
+    fn authorized_request(
+        &self,
+        method: http::Method,
+        path: &str,
+        body: Option<&Value>,
+        correlation_id: &str,
+    ) -> Result<http::Request<String>, http::Error> {
+        let access_token = self.tokens.access_token();
+        let request = protocol::api_request(&self.base_url, method, path, &access_token, body)?;
+        self.with_correlation_header(request, correlation_id)
+    }
+
+    fn record_response(&self, path: &str, res: &http::Response<String>) {
+        self.record_meta(ResponseMeta::new(path, res.status().as_u16(), res.headers()));
+    }
+
+    fn record_meta(&self, meta: ResponseMeta) {
+        if let Some(remaining) = meta.rate_limit_remaining {
+            debug!("{} requests left in the current rate limit window", remaining);
+        }
+        self.shared().last_response = Some(meta);
+    }
+
+    /// Assign a correlation id to a new logical operation
+    fn start_operation(&self) -> String {
+        let correlation_id = new_correlation_id();
+        self.shared().last_correlation_id = Some(correlation_id.clone());
+        correlation_id
+    }
+
+    fn with_correlation_header(
+        &self,
+        mut request: http::Request<String>,
+        correlation_id: &str,
+    ) -> Result<http::Request<String>, http::Error> {
+        if let Some(header) = &self.correlation_header {
+            request.headers_mut().insert(
+                http::HeaderName::from_bytes(header.as_bytes())?,
+                http::HeaderValue::from_str(correlation_id)?,
+            );
+        }
+        Ok(request)
+    }
+
+    fn record_server_time(&mut self, response: &DevicesResponse, request_started: SystemTime) {
+        let round_trip = request_started.elapsed().unwrap_or_default();
+        let midpoint = request_started + round_trip / 2;
+        let server_time = response.server_time();
+        let skew_ms = match server_time.duration_since(midpoint) {
+            Ok(ahead) => ahead.as_millis() as i64,
+            Err(behind) => -(behind.duration().as_millis() as i64),
+        };
+        debug!("Server clock skew: {} ms (round trip {:?})", skew_ms, round_trip);
+        self.server_time = Some(server_time);
+        self.clock_skew_ms = Some(skew_ms);
+    }
+
+    fn track_availability(&mut self, devices: &[Device]) {
+        let now = SystemTime::now();
+        // Anything longer than a few missed polls is downtime of the poller.
+        // Devices polled more slowly than this learn their own cadence.
+        let max_gap = self.polling_interval * 3;
+        for device in devices {
+            self.availability
+                .entry(device.id.clone())
+                .and_modify(|availability| availability.observe(device.online, now, max_gap))
+                .or_insert_with(|| DeviceAvailability::new(device.online, now));
+        }
+    }
+
+    /// Smoothed value of a temperature or humidity reading in wire units,
+    /// if smoothing is enabled and the device reports `code`
+    pub fn smoothed_value(&self, device_id: &str, code: impl Into<StatusCode>) -> Option<f32> {
+        self.smoothers
+            .get(&(device_id.to_string(), code.into()))
+            .and_then(Smoother::value)
+    }
+
+    /// Smoothed measured temperature of a device in the given unit
+    pub fn smoothed_temperature(&self, device_id: &str, unit: TemperatureUnit) -> Option<f32> {
+        self.smoothed_value(device_id, StatusCode::VaTemperature)
+            .or_else(|| self.smoothed_value(device_id, StatusCode::TempCurrent))
+            .map(|raw| unit.decode_deci_celsius(raw))
+    }
+
+    /// Smoothed relative humidity of a device in percent
+    pub fn smoothed_humidity(&self, device_id: &str) -> Option<f32> {
+        self.smoothed_value(device_id, StatusCode::VaHumidity)
+            .map(|raw| raw / 10.0)
+    }
+
+    fn smooth_readings(&mut self, devices: &[Device]) {
+        let Some(smoothing) = self.smoothing else {
+            return;
+        };
+        for device in devices {
+            for code in SMOOTHED_CODES {
+                if let Some(value) = device.status_value(&code).and_then(numeric_value) {
+                    self.smoothers
+                        .entry((device.id.clone(), code))
+                        .or_default()
+                        .push(smoothing.method, value);
+                }
+            }
+        }
+    }
+
+    fn detect_changes(&mut self, devices: &[Device]) {
+        for device in devices {
+            let Some(previous) = self.devices.iter().find(|old| old.id == device.id) else {
+                continue;
+            };
+            let mut diff = DeviceDiff::between(previous, device);
+            if let Some(smoothing) = self.smoothing {
+                diff.changes.retain(|change| {
+                    match self.smoothers.get_mut(&(device.id.clone(), change.code.clone())) {
+                        Some(smoother) => smoother.take_report(smoothing.min_change),
+                        None => true,
+                    }
+                });
+            }
+            if diff.is_empty() {
+                continue;
+            }
+            if diff.change(StatusCode::SwVersion).is_some() {
+                let old = previous.firmware_version();
+                let new = device.firmware_version();
+                info!("Firmware of {} changed from {:?} to {:?}", device.name, old, new);
+                self.events.push(DeviceEvent::FirmwareChanged {
+                    device_id: device.id.clone(),
+                    old,
+                    new,
+                });
+            }
+            self.events.push(DeviceEvent::StatusChanged(diff));
+        }
+    }
 }
@@ -0,0 +1,63 @@
+use crate::{bool_value, numeric_value, AllyApi, AllyError, Command, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// Load balancing state of a thermostat, shared between radiators in a room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadBalancing {
+    /// Whether load balancing is enabled (`load_balance_enable`)
+    pub enabled: Option<bool>,
+    /// Mean load of the room (`load_room_mean`)
+    pub room_mean: Option<i32>,
+    /// Load estimate of this radiator (`load_estimate`)
+    pub estimate: Option<i32>,
+}
+
+impl Device {
+    /// Load balancing state, if the device supports load balancing
+    pub fn load_balancing(&self) -> Option<LoadBalancing> {
+        if !self.capabilities().load_balancing {
+            return None;
+        }
+        let number = |code: StatusCode| {
+            self.status_value(code)
+                .and_then(numeric_value)
+                .map(|value| value.round() as i32)
+        };
+        Some(LoadBalancing {
+            enabled: self
+                .status_value(StatusCode::LoadBalanceEnable)
+                .and_then(bool_value),
+            room_mean: number(StatusCode::LoadRoomMean),
+            estimate: number(StatusCode::LoadEstimate),
+        })
+    }
+}
+
+impl AllyApi {
+    /// Enable or disable load balancing of a thermostat
+    pub async fn set_load_balancing(
+        &self,
+        device_id: &str,
+        enabled: bool,
+    ) -> Result<(), AllyError> {
+        self.send_commands(
+            device_id,
+            vec![Command::new(StatusCode::LoadBalanceEnable, enabled)],
+        )
+        .await
+    }
+
+    /// Send the mean load of the room to a thermostat, e.g. the average of
+    /// the `load_estimate` of every radiator in the room
+    pub async fn set_load_room_mean(
+        &self,
+        device_id: &str,
+        room_mean: i32,
+    ) -> Result<(), AllyError> {
+        self.send_commands(
+            device_id,
+            vec![Command::new(StatusCode::LoadRoomMean, room_mean)],
+        )
+        .await
+    }
+}
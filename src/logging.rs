@@ -0,0 +1,59 @@
+//! Log macros used throughout the crate
+//!
+//! With the `log` feature they forward to the `log` crate. Without it they
+//! still type check their arguments but compile to nothing.
+
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { ::log::trace!($($arg)*) };
+}
+
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)*) => { ::log::debug!($($arg)*) };
+}
+
+#[cfg(feature = "log")]
+macro_rules! info {
+    ($($arg:tt)*) => { ::log::info!($($arg)*) };
+}
+
+#[cfg(feature = "log")]
+macro_rules! warn {
+    ($($arg:tt)*) => { ::log::warn!($($arg)*) };
+}
+
+/// Whether debug messages are logged, to skip preparing them otherwise
+#[cfg(feature = "log")]
+macro_rules! debug_enabled {
+    () => {
+        ::log::log_enabled!(::log::Level::Debug)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {{ let _ = format_args!($($arg)*); }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {{ let _ = format_args!($($arg)*); }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! info {
+    ($($arg:tt)*) => {{ let _ = format_args!($($arg)*); }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)*) => {{ let _ = format_args!($($arg)*); }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug_enabled {
+    () => {
+        false
+    };
+}
@@ -0,0 +1,65 @@
+use crate::{AllyApi, AllyError, Command, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// Operating mode of a thermostat, the `mode` status code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Fixed setpoint, the schedule is ignored
+    Manual,
+    /// Follow the weekly schedule
+    AtHome,
+    /// Lowered temperature until someone comes back home
+    LeavingHome,
+    /// Heating paused
+    Pause,
+    /// Holiday at home, comfort temperature all day
+    HolidaySat,
+    /// Holiday away, lowered temperature all day
+    HolidaySatAway,
+}
+
+impl Device {
+    /// Current operating mode, if the device reports a known one
+    pub fn mode(&self) -> Option<Mode> {
+        self.status_value(StatusCode::Mode)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+}
+
+impl AllyApi {
+    /// Switch the operating mode of a thermostat
+    pub async fn set_mode(&self, device_id: &str, mode: Mode) -> Result<(), AllyError> {
+        let value = serde_json::to_value(mode).map_err(AllyError::Encode)?;
+        self.send_commands(device_id, vec![Command::new(StatusCode::Mode, value)])
+            .await
+    }
+
+    /// Switch a thermostat to its weekly schedule because someone is at home
+    pub async fn set_at_home(&self, device_id: &str) -> Result<(), AllyError> {
+        self.set_mode(device_id, Mode::AtHome).await
+    }
+
+    /// Lower the temperature of a thermostat until someone comes back home
+    pub async fn set_leaving_home(&self, device_id: &str) -> Result<(), AllyError> {
+        self.set_mode(device_id, Mode::LeavingHome).await
+    }
+
+    /// Switch every cached device that supports modes, e.g. to
+    /// [`Mode::LeavingHome`] when the last person leaves the house.
+    ///
+    /// Devices are switched one after another; the first failure stops the
+    /// remaining ones.
+    pub async fn set_mode_all(&self, mode: Mode) -> Result<(), AllyError> {
+        let ids: Vec<String> = self
+            .devices
+            .iter()
+            .filter(|device| device.capabilities().mode)
+            .map(|device| device.id.clone())
+            .collect();
+        for id in ids {
+            self.set_mode(&id, mode).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "dns")]
+use std::net::IpAddr;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+
+/// IP family tried first for connections to the API
+///
+/// Addresses of the other family are still tried if connecting over the
+/// preferred one fails, so a preference never makes the API unreachable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// Use the addresses in the order the resolver returns them
+    #[default]
+    Any,
+    /// Prefer IPv4, fall back to IPv6
+    V4,
+    /// Prefer IPv6, fall back to IPv4
+    V6,
+}
+
+/// Name resolution and connection settings of the HTTP client
+///
+/// Useful on networks with broken IPv6 or DNS interception, where the
+/// default behavior stalls.
+///
+/// ```
+/// use danfoss_ally_rs::{IpFamily, NetworkConfig};
+///
+/// let config = NetworkConfig::default()
+///     .pin("api.danfoss.com", "203.0.113.10:443".parse().unwrap())
+///     .ip_family(IpFamily::V4);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NetworkConfig {
+    /// Static addresses used instead of DNS lookups for the given host names
+    pub pinned_addresses: Vec<(String, SocketAddr)>,
+    /// IP family tried first for connections
+    pub ip_family: IpFamily,
+    /// DNS servers asked instead of the system resolver, on port 53. Only
+    /// with the `dns` feature
+    #[cfg(feature = "dns")]
+    pub dns_servers: Vec<IpAddr>,
+}
+
+impl NetworkConfig {
+    /// Resolve `host` to `address` instead of asking DNS
+    pub fn pin(mut self, host: &str, address: SocketAddr) -> Self {
+        self.pinned_addresses.push((host.to_string(), address));
+        self
+    }
+
+    /// Try addresses of one IP family first
+    pub fn ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.ip_family = ip_family;
+        self
+    }
+
+    /// Ask `server` instead of the system resolver. Servers are asked in the
+    /// order they were added.
+    #[cfg(feature = "dns")]
+    pub fn dns_server(mut self, server: IpAddr) -> Self {
+        self.dns_servers.push(server);
+        self
+    }
+
+    /// Apply the settings to a reqwest client builder
+    pub(crate) fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        for (host, address) in &self.pinned_addresses {
+            builder = builder.resolve(host, *address);
+        }
+        #[cfg(feature = "dns")]
+        let custom_servers = !self.dns_servers.is_empty();
+        #[cfg(not(feature = "dns"))]
+        let custom_servers = false;
+        if self.ip_family == IpFamily::Any && !custom_servers {
+            return builder;
+        }
+        builder.dns_resolver(Arc::new(Resolver {
+            ip_family: self.ip_family,
+            #[cfg(feature = "dns")]
+            servers: custom_servers.then(|| Arc::new(servers::Servers::new(&self.dns_servers))),
+        }))
+    }
+}
+
+/// Resolver ordering the addresses by the preferred IP family
+struct Resolver {
+    ip_family: IpFamily,
+    #[cfg(feature = "dns")]
+    servers: Option<Arc<servers::Servers>>,
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let ip_family = self.ip_family;
+        #[cfg(feature = "dns")]
+        let servers = self.servers.clone();
+        Box::pin(async move {
+            #[cfg(feature = "dns")]
+            if let Some(servers) = servers {
+                let addresses = servers.lookup(name.as_str()).await?;
+                return Ok(Box::new(prefer(addresses, ip_family).into_iter()) as Addrs);
+            }
+            let host = name.as_str().to_string();
+            let addresses =
+                tokio::task::spawn_blocking(move || (host.as_str(), 0).to_socket_addrs())
+                    .await??
+                    .collect();
+            Ok(Box::new(prefer(addresses, ip_family).into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Move the addresses of the preferred family to the front, keeping the
+/// order within each family
+fn prefer(mut addresses: Vec<SocketAddr>, ip_family: IpFamily) -> Vec<SocketAddr> {
+    match ip_family {
+        IpFamily::Any => {}
+        IpFamily::V4 => addresses.sort_by_key(SocketAddr::is_ipv6),
+        IpFamily::V6 => addresses.sort_by_key(SocketAddr::is_ipv4),
+    }
+    addresses
+}
+
+#[cfg(feature = "dns")]
+mod servers {
+    use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+    use hickory_resolver::TokioAsyncResolver;
+    use std::net::{IpAddr, SocketAddr};
+    use std::sync::OnceLock;
+
+    /// Resolver asking the configured DNS servers, created on first use
+    /// because it needs a tokio runtime
+    pub(super) struct Servers {
+        config: ResolverConfig,
+        resolver: OnceLock<TokioAsyncResolver>,
+    }
+
+    impl Servers {
+        pub(super) fn new(servers: &[IpAddr]) -> Self {
+            let group = NameServerConfigGroup::from_ips_clear(servers, 53, true);
+            Self {
+                config: ResolverConfig::from_parts(None, vec![], group),
+                resolver: OnceLock::new(),
+            }
+        }
+
+        pub(super) async fn lookup(
+            &self,
+            host: &str,
+        ) -> Result<Vec<SocketAddr>, hickory_resolver::error::ResolveError> {
+            let resolver = self.resolver.get_or_init(|| {
+                TokioAsyncResolver::tokio(self.config.clone(), ResolverOpts::default())
+            });
+            let lookup = resolver.lookup_ip(host).await?;
+            Ok(lookup.iter().map(|ip| SocketAddr::new(ip, 0)).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preferred_family_comes_first() {
+        let addresses: Vec<SocketAddr> = [
+            "[2001:db8::1]:0",
+            "192.0.2.1:0",
+            "[2001:db8::2]:0",
+            "192.0.2.2:0",
+        ]
+        .iter()
+        .map(|address| address.parse().unwrap())
+        .collect();
+
+        let v4 = prefer(addresses.clone(), IpFamily::V4);
+        assert_eq!(v4, [addresses[1], addresses[3], addresses[0], addresses[2]]);
+        let v6 = prefer(addresses.clone(), IpFamily::V6);
+        assert_eq!(v6, [addresses[0], addresses[2], addresses[1], addresses[3]]);
+        assert_eq!(prefer(addresses.clone(), IpFamily::Any), addresses);
+    }
+
+    #[tokio::test]
+    async fn resolver_falls_back_to_the_other_family() {
+        let resolver = Resolver {
+            ip_family: IpFamily::V6,
+            #[cfg(feature = "dns")]
+            servers: None,
+        };
+        let addresses: Vec<SocketAddr> = resolver
+            .resolve("127.0.0.1".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(addresses, ["127.0.0.1:0".parse().unwrap()]);
+    }
+}
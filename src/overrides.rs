@@ -0,0 +1,100 @@
+use crate::AllyApi;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Settings of a single device that take precedence over the global ones
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceOverrides {
+    /// How often this device should be refreshed
+    pub polling_interval: Option<Duration>,
+    /// Preferred comfort temperature in degrees Celsius
+    pub comfort_temperature: Option<f32>,
+    /// Battery level in percent below which the battery counts as low
+    pub low_battery_threshold: Option<u8>,
+    /// Lowest acceptable measured temperature in degrees Celsius
+    pub min_temperature: Option<f32>,
+    /// Highest acceptable measured temperature in degrees Celsius
+    pub max_temperature: Option<f32>,
+    /// Room the device belongs to, taking precedence over the `room` tag
+    pub room: Option<String>,
+}
+
+/// An invalid entry in [`AllyApi::device_overrides`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideError {
+    /// Device id or alias the offending entry is keyed by
+    pub key: String,
+    /// What is wrong with the entry
+    pub message: String,
+}
+
+impl fmt::Display for OverrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "device override '{}': {}", self.key, self.message)
+    }
+}
+
+impl std::error::Error for OverrideError {}
+
+impl AllyApi {
+    /// Overrides of a device, looked up by its id or any of its aliases
+    pub fn overrides_for(&self, device_id: &str) -> Option<&DeviceOverrides> {
+        self.device_overrides.get(device_id).or_else(|| {
+            self.aliases
+                .iter()
+                .filter(|(_, id)| id.as_str() == device_id)
+                .find_map(|(alias, _)| self.device_overrides.get(alias))
+        })
+    }
+
+    /// Check all device overrides, reporting every invalid entry.
+    ///
+    /// Keys must name a known device id or alias, so call this after the
+    /// first [`AllyApi::get_devices`].
+    pub fn validate_overrides(&self) -> Result<(), Vec<OverrideError>> {
+        let mut errors = vec![];
+        for (key, overrides) in &self.device_overrides {
+            let mut fail = |message: String| {
+                errors.push(OverrideError {
+                    key: key.clone(),
+                    message,
+                })
+            };
+            let id = self.aliases.get(key).unwrap_or(key);
+            if !self.devices.iter().any(|device| &device.id == id) {
+                fail("no device with this id or alias".to_string());
+            }
+            if overrides.polling_interval == Some(Duration::ZERO) {
+                fail("polling_interval must be greater than zero".to_string());
+            }
+            if let Some(temperature) = overrides.comfort_temperature {
+                if !(5.0..=35.0).contains(&temperature) {
+                    fail(format!(
+                        "comfort_temperature {} is outside 5-35 °C",
+                        temperature
+                    ));
+                }
+            }
+            if let Some(threshold) = overrides.low_battery_threshold {
+                if threshold > 100 {
+                    fail(format!("low_battery_threshold {} exceeds 100 %", threshold));
+                }
+            }
+            if let (Some(min), Some(max)) = (overrides.min_temperature, overrides.max_temperature) {
+                if min > max {
+                    fail(format!(
+                        "min_temperature {} is above max_temperature {}",
+                        min, max
+                    ));
+                }
+            }
+        }
+        errors.sort_by(|a, b| a.key.cmp(&b.key));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::Device;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A single [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON Patch operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Add a value at `path`
+    Add {
+        /// JSON pointer to the target location
+        path: String,
+        /// Value to add
+        value: Value,
+    },
+    /// Remove the value at `path`
+    Remove {
+        /// JSON pointer to the target location
+        path: String,
+    },
+    /// Replace the value at `path`
+    Replace {
+        /// JSON pointer to the target location
+        path: String,
+        /// New value
+        value: Value,
+    },
+}
+
+/// JSON document describing the state of all devices, keyed by device id.
+///
+/// The status list of each device is turned into an object keyed by status
+/// code, so patches address single values such as `/abc/status/temp_set`.
+pub fn snapshot(devices: &[Device]) -> Value {
+    let mut snapshot = Map::new();
+    for device in devices {
+        let mut value = serde_json::to_value(device).unwrap_or(Value::Null);
+        if let Value::Object(fields) = &mut value {
+            let status: Map<String, Value> = device
+                .status
+                .iter()
+                .map(|status| (status.code.to_string(), status.value.clone()))
+                .collect();
+            fields.insert("status".to_string(), Value::Object(status));
+        }
+        snapshot.insert(device.id.clone(), value);
+    }
+    Value::Object(snapshot)
+}
+
+/// Operations that turn the document `old` into `new`
+///
+/// Objects are compared key by key, any other differing value is replaced
+/// as a whole.
+///
+/// ```
+/// use danfoss_ally_rs::{json_patch, PatchOperation};
+/// use serde_json::json;
+///
+/// let patch = json_patch(&json!({"a": {"b": 1}}), &json!({"a": {"b": 2}}));
+/// assert_eq!(
+///     patch,
+///     vec![PatchOperation::Replace { path: "/a/b".to_string(), value: json!(2) }]
+/// );
+/// ```
+pub fn json_patch(old: &Value, new: &Value) -> Vec<PatchOperation> {
+    let mut operations = vec![];
+    diff_into(&mut operations, String::new(), old, new);
+    operations
+}
+
+fn diff_into(operations: &mut Vec<PatchOperation>, path: String, old: &Value, new: &Value) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}/{}", path, escape(key));
+                match new.get(key) {
+                    Some(new_value) => diff_into(operations, child, old_value, new_value),
+                    None => operations.push(PatchOperation::Remove { path: child }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    operations.push(PatchOperation::Add {
+                        path: format!("{}/{}", path, escape(key)),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        _ if old != new => operations.push(PatchOperation::Replace {
+            path,
+            value: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Status, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn equal_documents_need_no_operations() {
+        let document = json!({"a": {"b": [1, 2]}, "c": null});
+        assert!(json_patch(&document, &document).is_empty());
+    }
+
+    #[test]
+    fn objects_are_compared_key_by_key() {
+        let old = json!({"kept": 1, "removed": true, "nested": {"x": 1}});
+        let new = json!({"kept": 1, "nested": {"x": 2}, "added": "yes"});
+        assert_eq!(
+            json_patch(&old, &new),
+            vec![
+                PatchOperation::Replace {
+                    path: "/nested/x".to_string(),
+                    value: json!(2)
+                },
+                PatchOperation::Remove {
+                    path: "/removed".to_string()
+                },
+                PatchOperation::Add {
+                    path: "/added".to_string(),
+                    value: json!("yes")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn other_values_are_replaced_as_a_whole() {
+        assert_eq!(
+            json_patch(&json!({"list": [1, 2]}), &json!({"list": [1, 3]})),
+            vec![PatchOperation::Replace {
+                path: "/list".to_string(),
+                value: json!([1, 3])
+            }]
+        );
+        assert_eq!(
+            json_patch(&json!(1), &json!({"a": 1})),
+            vec![PatchOperation::Replace {
+                path: String::new(),
+                value: json!({"a": 1})
+            }]
+        );
+    }
+
+    #[test]
+    fn keys_are_escaped_in_paths() {
+        let patch = json_patch(&json!({}), &json!({"a/b~c": 1}));
+        assert_eq!(
+            patch,
+            vec![PatchOperation::Add {
+                path: "/a~1b~0c".to_string(),
+                value: json!(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn snapshot_keys_devices_by_id_and_status_by_code() {
+        let device = Device {
+            id: "abc".to_string(),
+            status: vec![Status {
+                code: StatusCode::TempSet,
+                value: json!(215),
+            }],
+            ..Device::default()
+        };
+        let before = snapshot(std::slice::from_ref(&device));
+        assert_eq!(before["abc"]["status"], json!({"temp_set": 215}));
+
+        let mut changed = device;
+        changed.status[0].value = json!(220);
+        assert_eq!(
+            json_patch(&before, &snapshot(&[changed])),
+            vec![PatchOperation::Replace {
+                path: "/abc/status/temp_set".to_string(),
+                value: json!(220)
+            }]
+        );
+    }
+
+    #[test]
+    fn operations_serialize_as_rfc_6902() {
+        let operation = PatchOperation::Remove {
+            path: "/abc".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(operation).unwrap(),
+            json!({"op": "remove", "path": "/abc"})
+        );
+    }
+}
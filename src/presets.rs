@@ -0,0 +1,105 @@
+use crate::{AllyApi, AllyError, BatchResults};
+use serde::{Deserialize, Serialize};
+
+/// Named comfort level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Reduced temperature, e.g. at night
+    Eco,
+    /// Regular temperature while people are at home
+    Comfort,
+    /// Low temperature while nobody is at home
+    Away,
+}
+
+/// Target temperatures in degrees Celsius for each [`Preset`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PresetTemperatures {
+    /// Temperature of [`Preset::Eco`]
+    pub eco: f32,
+    /// Temperature of [`Preset::Comfort`]
+    pub comfort: f32,
+    /// Temperature of [`Preset::Away`]
+    pub away: f32,
+}
+
+impl Default for PresetTemperatures {
+    fn default() -> Self {
+        Self {
+            eco: 18.0,
+            comfort: 21.0,
+            away: 15.0,
+        }
+    }
+}
+
+impl PresetTemperatures {
+    /// Temperature of a preset
+    pub fn get(&self, preset: Preset) -> f32 {
+        match preset {
+            Preset::Eco => self.eco,
+            Preset::Comfort => self.comfort,
+            Preset::Away => self.away,
+        }
+    }
+}
+
+impl AllyApi {
+    /// Target temperature of a preset for a device in degrees Celsius.
+    ///
+    /// A comfort temperature in the device overrides wins, then the
+    /// temperatures of the device's room, then the house-wide defaults.
+    pub fn preset_temperature(&self, device_id: &str, preset: Preset) -> f32 {
+        let comfort_override = self
+            .overrides_for(device_id)
+            .and_then(|overrides| overrides.comfort_temperature)
+            .filter(|_| preset == Preset::Comfort);
+        comfort_override.unwrap_or_else(|| {
+            self.room_of(device_id)
+                .and_then(|room| self.room_presets.get(room))
+                .unwrap_or(&self.presets)
+                .get(preset)
+        })
+    }
+
+    /// Setpoints in degrees Celsius a preset resolves to, for every device
+    /// with an adjustable setpoint in `room`, or in the whole house if `room`
+    /// is `None`
+    pub fn preset_setpoints(&self, room: Option<&str>, preset: Preset) -> Vec<(String, f32)> {
+        self.devices
+            .iter()
+            .filter(|device| device.capabilities().setpoint)
+            .filter(|device| room.is_none() || self.room_of(&device.id) == room)
+            .map(|device| {
+                (
+                    device.id.clone(),
+                    self.preset_temperature(&device.id, preset),
+                )
+            })
+            .collect()
+    }
+
+    /// Send the setpoints of [`AllyApi::preset_setpoints`] to the devices,
+    /// see [`AllyApi::send_commands_batch`] for the returned results.
+    ///
+    /// Setpoints a device would not accept are not sent; their results
+    /// follow the sent ones.
+    pub async fn apply_preset(
+        &self,
+        room: Option<&str>,
+        preset: Preset,
+    ) -> Result<BatchResults, AllyError> {
+        let mut batch = vec![];
+        let mut rejected: BatchResults = vec![];
+        for (device_id, celsius) in self.preset_setpoints(room, preset) {
+            match self.setpoint_command(&device_id, celsius) {
+                Ok(command) => batch.push((device_id, vec![command])),
+                Err(e) => rejected.push((device_id, Err(e.into()))),
+            }
+        }
+        let mut results = self.send_commands_batch(batch).await?;
+        results.append(&mut rejected);
+        Ok(results)
+    }
+}
@@ -0,0 +1,192 @@
+//! Request construction and response parsing of the Danfoss Ally API,
+//! without any I/O
+//!
+//! [`AllyApi`](crate::AllyApi) drives these functions and adds token
+//! handling, retries and rate limiting on top. They can also be used on
+//! their own, with any HTTP client or in tests.
+//!
+//! ```
+//! use danfoss_ally_rs::{http, protocol, Token};
+//!
+//! let request = protocol::token_request("https://api.danfoss.com", "key", "secret").unwrap();
+//! assert_eq!(request.uri().path(), protocol::TOKEN_PATH);
+//!
+//! let response = http::Response::builder()
+//!     .status(200)
+//!     .body(r#"{"access_token":"abc","token_type":"Bearer","expires_in":"3599"}"#.to_string())
+//!     .unwrap();
+//! let token: Token = protocol::parse_response(protocol::TOKEN_PATH, &response).unwrap();
+//! assert_eq!(token.access_token, "abc");
+//! ```
+
+use crate::{parse_retry_after, AllyError, ApiError, Command, CommandRejected};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt;
+
+/// Path of the token endpoint
+pub const TOKEN_PATH: &str = "/oauth2/token";
+
+/// Path of the endpoint listing all devices
+pub const DEVICES_PATH: &str = "/ally/devices";
+
+/// Path of a single device. The id is percent-encoded, see
+/// [`InvalidDeviceId`] for the ids that are rejected.
+///
+/// ```
+/// use danfoss_ally_rs::protocol;
+///
+/// assert_eq!(
+///     protocol::device_path("a/b?c").unwrap(),
+///     "/ally/devices/a%2Fb%3Fc"
+/// );
+/// assert!(protocol::device_path("..").is_err());
+/// ```
+pub fn device_path(device_id: &str) -> Result<String, InvalidDeviceId> {
+    Ok(format!("{}/{}", DEVICES_PATH, device_segment(device_id)?))
+}
+
+/// Path of the status values of a device
+pub fn device_status_path(device_id: &str) -> Result<String, InvalidDeviceId> {
+    Ok(format!(
+        "{}/{}/status",
+        DEVICES_PATH,
+        device_segment(device_id)?
+    ))
+}
+
+/// Path commands of a device are posted to
+pub fn device_commands_path(device_id: &str) -> Result<String, InvalidDeviceId> {
+    Ok(format!(
+        "{}/{}/commands",
+        DEVICES_PATH,
+        device_segment(device_id)?
+    ))
+}
+
+/// Error returned for a device id that cannot be used as a path segment:
+/// an empty id, `.` or `..`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDeviceId {
+    /// The rejected id
+    pub device_id: String,
+}
+
+impl fmt::Display for InvalidDeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid device id {:?}", self.device_id)
+    }
+}
+
+impl Error for InvalidDeviceId {}
+
+/// Percent-encode a device id as a single path segment, so ids containing
+/// `/`, `?` or `#` cannot reach other endpoints. Empty ids and dot segments
+/// would still be resolved against the path and are rejected.
+fn device_segment(device_id: &str) -> Result<String, InvalidDeviceId> {
+    if matches!(device_id, "" | "." | "..") {
+        return Err(InvalidDeviceId {
+            device_id: device_id.to_string(),
+        });
+    }
+    Ok(device_id
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect())
+}
+
+/// Response of the commands endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandsResponse {
+    /// Whether the API applied the commands
+    pub result: bool,
+}
+
+impl CommandsResponse {
+    /// Fail with [`CommandRejected`] if the API did not apply the commands
+    pub fn into_result(self, device_id: &str) -> Result<(), AllyError> {
+        if !self.result {
+            return Err(CommandRejected {
+                device_id: device_id.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Request for a new access token with the client credentials grant
+pub fn token_request(
+    base_url: &str,
+    api_key: &str,
+    api_secret: &str,
+) -> Result<http::Request<String>, http::Error> {
+    let basic_auth = base64::encode(format!("{}:{}", api_key, api_secret));
+    http::Request::post(format!("{}{}", base_url, TOKEN_PATH))
+        .header("content-type", "application/x-www-form-urlencoded")
+        .header("accept", "application/json")
+        .header("authorization", format!("Basic {}", basic_auth))
+        .body("grant_type=client_credentials".to_string())
+}
+
+/// Request to an API path carrying the access token and an optional JSON body
+pub fn api_request(
+    base_url: &str,
+    method: http::Method,
+    path: &str,
+    access_token: &str,
+    body: Option<&Value>,
+) -> Result<http::Request<String>, http::Error> {
+    let request = http::Request::builder()
+        .method(method)
+        .uri(format!("{}{}", base_url, path))
+        .header("accept", "application/json")
+        .header("authorization", format!("Bearer {}", access_token));
+    match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(body.to_string()),
+        None => request.body(String::new()),
+    }
+}
+
+/// Body that posts `commands` to [`device_commands_path`]
+pub fn commands_body(commands: &[Command]) -> Value {
+    json!({ "commands": commands })
+}
+
+/// Read the JSON body of a response to `path`. Unsuccessful statuses become
+/// the matching [`AllyError`], see [`ApiError`].
+pub fn parse_response<T: DeserializeOwned>(
+    path: &str,
+    response: &http::Response<String>,
+) -> Result<T, AllyError> {
+    let body = response.body();
+    if !response.status().is_success() {
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        return Err(ApiError::new(path, response.status().as_u16(), body)
+            .with_retry_after(retry_after)
+            .into());
+    }
+    serde_json::from_str(body).map_err(|source| AllyError::deserialize(path, body, source))
+}
+
+/// Read the response to commands posted to a device
+pub fn parse_commands_response(
+    device_id: &str,
+    response: &http::Response<String>,
+) -> Result<(), AllyError> {
+    parse_response::<CommandsResponse>(&device_commands_path(device_id)?, response)?
+        .into_result(device_id)
+}
@@ -0,0 +1,486 @@
+use crate::runtime::{self, Instant, SystemTime, UNIX_EPOCH};
+use crate::{AllyApi, AllyError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// Minimum time between writes of the counters by the client
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to do once a configurable share of the quota is used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaAction {
+    /// Only log a warning
+    Warn,
+    /// Delay every further request by the given duration
+    SlowDown(Duration),
+    /// Refuse further requests with [`QuotaExceeded`] until the period rolls over
+    HardStop,
+}
+
+/// Outcome of checking the quota before a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaDecision {
+    /// Send the request right away
+    Proceed,
+    /// Wait before sending the request
+    Delay(Duration),
+    /// Do not send the request
+    Refuse,
+}
+
+/// Error returned when a request is refused because the quota is used up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// Calls made in the current period
+    pub calls: u64,
+    /// Limit of the current period
+    pub limit: u64,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "API quota exhausted: {} of {} calls used",
+            self.calls, self.limit
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Counts calls against the daily and monthly quota of the developer plan
+///
+/// Periods follow UTC calendar days and months. When created with
+/// [`QuotaTracker::load_or_new`] the counters are written back to the file,
+/// so they survive restarts. The client writes them in the background at
+/// most every few seconds; call [`AllyApi::save_quota`] before exiting to
+/// keep the latest calls.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaTracker {
+    /// Maximum number of calls per day
+    pub daily_limit: Option<u64>,
+    /// Maximum number of calls per month
+    pub monthly_limit: Option<u64>,
+    /// Share of a limit in percent after which `action` applies. Default: 90
+    pub threshold_percent: f64,
+    /// What to do once the threshold is crossed. Default: warn
+    pub action: QuotaAction,
+    /// Calls made on the current day
+    pub daily_calls: u64,
+    /// Calls made in the current month
+    pub monthly_calls: u64,
+    day: u64,
+    month: u64,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Calls were counted since the counters were last written
+    #[serde(skip)]
+    unsaved: bool,
+    #[serde(skip)]
+    last_saved: Option<Instant>,
+    /// Number of changes to the counters, to order concurrent writes
+    #[serde(skip)]
+    generation: u64,
+    #[serde(skip)]
+    writer: FileWriter,
+}
+
+/// Serializes the writes of a tracker and its copies and remembers the newest
+/// generation written, so an older copy never overwrites a newer one
+#[derive(Debug, Clone, Default)]
+struct FileWriter(Arc<Mutex<u64>>);
+
+impl PartialEq for FileWriter {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl QuotaTracker {
+    /// Track calls against the given limits, without persistence
+    pub fn new(daily_limit: Option<u64>, monthly_limit: Option<u64>) -> Self {
+        let (day, month) = period(SystemTime::now());
+        Self {
+            daily_limit,
+            monthly_limit,
+            threshold_percent: 90.0,
+            action: QuotaAction::Warn,
+            daily_calls: 0,
+            monthly_calls: 0,
+            day,
+            month,
+            path: None,
+            unsaved: false,
+            last_saved: None,
+            generation: 0,
+            writer: FileWriter::default(),
+        }
+    }
+
+    /// Load the counters from `path`, or start new ones if the file does not
+    /// exist yet or cannot be read as counters, e.g. after a crash while it
+    /// was written. The limits passed in replace the stored ones.
+    pub fn load_or_new(
+        path: impl AsRef<Path>,
+        daily_limit: Option<u64>,
+        monthly_limit: Option<u64>,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut tracker = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring unreadable API quota counters in {}. {}",
+                    path.display(),
+                    e
+                );
+                Self::new(None, None)
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::new(None, None),
+            Err(e) => return Err(e),
+        };
+        tracker.daily_limit = daily_limit;
+        tracker.monthly_limit = monthly_limit;
+        tracker.path = Some(path.to_path_buf());
+        Ok(tracker)
+    }
+
+    /// Write the counters to the file they were loaded from, if any.
+    ///
+    /// The file is replaced atomically. Writes of copies of the tracker are
+    /// serialized, and a copy older than the counters already written is
+    /// not written.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut written = self.writer.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.generation > 0 && self.generation <= *written {
+            return Ok(());
+        }
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temporary, path)?;
+        *written = self.generation;
+        Ok(())
+    }
+
+    /// Calls left today, if a daily limit is set
+    pub fn remaining_today(&self) -> Option<u64> {
+        self.daily_limit
+            .map(|limit| limit.saturating_sub(self.daily_calls))
+    }
+
+    /// Calls left this month, if a monthly limit is set
+    pub fn remaining_this_month(&self) -> Option<u64> {
+        self.monthly_limit
+            .map(|limit| limit.saturating_sub(self.monthly_calls))
+    }
+
+    /// Decide whether a request may be sent now
+    pub fn check(&mut self, now: SystemTime) -> QuotaDecision {
+        self.roll_over(now);
+        let over_threshold = [
+            (self.daily_calls, self.daily_limit),
+            (self.monthly_calls, self.monthly_limit),
+        ]
+        .iter()
+        .any(|(calls, limit)| match limit {
+            Some(limit) => *calls as f64 >= *limit as f64 * self.threshold_percent / 100.0,
+            None => false,
+        });
+        if !over_threshold {
+            return QuotaDecision::Proceed;
+        }
+        match self.action {
+            QuotaAction::Warn => QuotaDecision::Proceed,
+            QuotaAction::SlowDown(delay) => QuotaDecision::Delay(delay),
+            QuotaAction::HardStop => QuotaDecision::Refuse,
+        }
+    }
+
+    /// Count a call made at `now` and persist the counters
+    pub fn record_call(&mut self, now: SystemTime) -> io::Result<()> {
+        self.count_call(now);
+        self.unsaved = false;
+        self.save()
+    }
+
+    /// Check the quota and count the call unless it is refused, in one step
+    /// so concurrent requests cannot overshoot the limit
+    pub(crate) fn admit(&mut self, now: SystemTime) -> QuotaDecision {
+        let decision = self.check(now);
+        if decision != QuotaDecision::Refuse {
+            self.count_call(now);
+        }
+        decision
+    }
+
+    /// Copy of the counters to write to the file, if they changed and were
+    /// not written within the last few seconds
+    pub(crate) fn pending_save(&mut self) -> Option<QuotaTracker> {
+        let due = self
+            .last_saved
+            .is_none_or(|saved| saved.elapsed() >= SAVE_INTERVAL);
+        if self.path.is_none() || !self.unsaved || !due {
+            return None;
+        }
+        self.unsaved = false;
+        self.last_saved = Some(Instant::now());
+        Some(self.clone())
+    }
+
+    fn count_call(&mut self, now: SystemTime) {
+        self.roll_over(now);
+        self.daily_calls += 1;
+        self.monthly_calls += 1;
+        self.unsaved = true;
+        self.generation += 1;
+    }
+
+    /// The exhausted period, reported when a request is refused
+    pub fn exceeded(&self) -> QuotaExceeded {
+        let daily = (self.daily_calls, self.daily_limit.unwrap_or(u64::MAX));
+        let monthly = (self.monthly_calls, self.monthly_limit.unwrap_or(u64::MAX));
+        let (calls, limit) =
+            if daily.0 as f64 / daily.1 as f64 >= monthly.0 as f64 / monthly.1 as f64 {
+                daily
+            } else {
+                monthly
+            };
+        QuotaExceeded { calls, limit }
+    }
+
+    /// Take over the counters of `saved` if they belong to the current period
+    pub(crate) fn merge_counters(&mut self, saved: &QuotaTracker) {
+        self.roll_over(SystemTime::now());
+        if saved.day == self.day {
+            self.daily_calls = self.daily_calls.max(saved.daily_calls);
+        }
+        if saved.month == self.month {
+            self.monthly_calls = self.monthly_calls.max(saved.monthly_calls);
+        }
+    }
+
+    fn roll_over(&mut self, now: SystemTime) {
+        let (day, month) = period(now);
+        if day != self.day {
+            self.day = day;
+            self.daily_calls = 0;
+        }
+        if month != self.month {
+            self.month = month;
+            self.monthly_calls = 0;
+        }
+    }
+}
+
+impl AllyApi {
+    /// Track calls against the plan's request quota. Clones of the client
+    /// share the tracker. Default: None
+    pub fn set_quota(&self, quota: Option<QuotaTracker>) {
+        self.shared().quota = quota;
+    }
+
+    /// Copy of the quota tracker, `None` if none is configured
+    pub fn quota(&self) -> Option<QuotaTracker> {
+        self.shared().quota.clone()
+    }
+
+    /// Write the quota counters to their file now, e.g. before exiting
+    pub fn save_quota(&self) -> io::Result<()> {
+        let quota = self.shared().quota.clone();
+        match quota {
+            Some(quota) => quota.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the quota before a request and count the request. The counters
+    /// are written to their file in the background.
+    pub(crate) async fn count_request(&self) -> Result<(), AllyError> {
+        let (decision, exceeded, pending) = match self.shared().quota.as_mut() {
+            Some(quota) => {
+                let decision = quota.admit(SystemTime::now());
+                let exceeded = (decision == QuotaDecision::Refuse).then(|| quota.exceeded());
+                (decision, exceeded, quota.pending_save())
+            }
+            None => return Ok(()),
+        };
+        if let Some(quota) = pending {
+            runtime::spawn_blocking(move || {
+                if let Err(e) = quota.save() {
+                    warn!("Could not persist API quota counters. {:?}", e);
+                }
+            });
+        }
+        if let Some(exceeded) = exceeded {
+            return Err(exceeded.into());
+        }
+        if let QuotaDecision::Delay(delay) = decision {
+            warn!("API quota nearly used up, delaying request by {:?}", delay);
+            runtime::sleep(delay).await;
+        }
+        Ok(())
+    }
+}
+
+/// UTC day and month index of a point in time
+fn period(time: SystemTime) -> (u64, u64) {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86400;
+    // Civil-from-days conversion, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (days, year * 12 + month - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86400;
+
+    /// 2024-01-31 12:00 UTC
+    fn jan_31() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(19_753 * DAY + DAY / 2)
+    }
+
+    fn tracker_at(now: SystemTime, daily: u64, monthly: u64) -> QuotaTracker {
+        let mut tracker = QuotaTracker::new(Some(daily), Some(monthly));
+        tracker.roll_over(now);
+        tracker
+    }
+
+    #[test]
+    fn periods_follow_utc_days_and_months() {
+        let (day, month) = period(jan_31());
+        assert_eq!(day, 19_753);
+        assert_eq!(month, 2024 * 12);
+        let (next_day, next_month) = period(jan_31() + Duration::from_secs(DAY));
+        assert_eq!(next_day, day + 1);
+        assert_eq!(next_month, month + 1);
+    }
+
+    #[test]
+    fn daily_counter_rolls_over_at_midnight() {
+        let now = jan_31() - Duration::from_secs(DAY);
+        let mut tracker = tracker_at(now, 10, 100);
+        tracker.record_call(now).unwrap();
+        tracker.record_call(now).unwrap();
+        tracker.record_call(now + Duration::from_secs(DAY)).unwrap();
+        assert_eq!(tracker.daily_calls, 1);
+        assert_eq!(tracker.monthly_calls, 3);
+    }
+
+    #[test]
+    fn monthly_counter_rolls_over_with_the_month() {
+        let mut tracker = tracker_at(jan_31(), 10, 100);
+        tracker.record_call(jan_31()).unwrap();
+        tracker
+            .record_call(jan_31() + Duration::from_secs(DAY))
+            .unwrap();
+        assert_eq!(tracker.daily_calls, 1);
+        assert_eq!(tracker.monthly_calls, 1);
+    }
+
+    #[test]
+    fn hard_stop_refuses_at_the_threshold() {
+        let now = jan_31();
+        let mut tracker = tracker_at(now, 2, 100);
+        tracker.threshold_percent = 100.0;
+        tracker.action = QuotaAction::HardStop;
+        assert_eq!(tracker.admit(now), QuotaDecision::Proceed);
+        assert_eq!(tracker.admit(now), QuotaDecision::Proceed);
+        assert_eq!(tracker.admit(now), QuotaDecision::Refuse);
+        assert_eq!(tracker.daily_calls, 2);
+        assert_eq!(tracker.exceeded(), QuotaExceeded { calls: 2, limit: 2 });
+        assert_eq!(
+            tracker.admit(now + Duration::from_secs(DAY)),
+            QuotaDecision::Proceed
+        );
+    }
+
+    #[test]
+    fn slow_down_delays_past_the_threshold() {
+        let now = jan_31();
+        let mut tracker = tracker_at(now, 10, 100);
+        tracker.threshold_percent = 50.0;
+        tracker.action = QuotaAction::SlowDown(Duration::from_secs(2));
+        for _ in 0..5 {
+            assert_eq!(tracker.admit(now), QuotaDecision::Proceed);
+        }
+        assert_eq!(
+            tracker.admit(now),
+            QuotaDecision::Delay(Duration::from_secs(2))
+        );
+    }
+
+    fn temporary_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "danfoss-ally-quota-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn counters_survive_a_save_and_reload() {
+        let path = temporary_file("reload");
+        let mut tracker = QuotaTracker::load_or_new(&path, Some(10), None).unwrap();
+        tracker.record_call(SystemTime::now()).unwrap();
+        tracker.record_call(SystemTime::now()).unwrap();
+        tracker.save().unwrap();
+
+        let reloaded = QuotaTracker::load_or_new(&path, Some(20), None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.daily_calls, 2);
+        assert_eq!(reloaded.monthly_calls, 2);
+        assert_eq!(reloaded.daily_limit, Some(20));
+    }
+
+    #[test]
+    fn older_copies_do_not_overwrite_newer_counters() {
+        let path = temporary_file("order");
+        let mut tracker = QuotaTracker::load_or_new(&path, Some(10), None).unwrap();
+        tracker.record_call(SystemTime::now()).unwrap();
+        let older = tracker.clone();
+        tracker.record_call(SystemTime::now()).unwrap();
+        tracker.save().unwrap();
+        older.save().unwrap();
+
+        let reloaded = QuotaTracker::load_or_new(&path, None, None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.daily_calls, 2);
+    }
+
+    #[test]
+    fn corrupt_file_starts_new_counters() {
+        let path = temporary_file("corrupt");
+        fs::write(&path, r#"{"daily_calls": 4"#).unwrap();
+
+        let tracker = QuotaTracker::load_or_new(&path, Some(10), None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tracker.daily_calls, 0);
+        assert_eq!(tracker.path.as_deref(), Some(path.as_path()));
+    }
+}
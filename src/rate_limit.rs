@@ -0,0 +1,151 @@
+use crate::runtime::{self, Instant};
+use crate::AllyApi;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Maximum number of requests within a period, enforced with a token bucket
+/// that allows bursts of up to `requests` requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests allowed per period
+    pub requests: u32,
+    /// Length of the period
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Allow `requests` requests per second
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(1),
+        }
+    }
+
+    /// Allow `requests` requests per minute
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Token bucket of a [`RateLimit`]
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+    limit: Option<RateLimit>,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Default for TokenBucket {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            tokens: 0.0,
+            updated: Instant::now(),
+        }
+    }
+}
+
+impl TokenBucket {
+    /// Take a token for one request and return how long to wait before
+    /// sending it. The bucket starts over when the limit changes.
+    fn reserve(&mut self, limit: RateLimit, now: Instant) -> Duration {
+        if limit.requests == 0 || limit.per.is_zero() {
+            return Duration::ZERO;
+        }
+        let capacity = f64::from(limit.requests);
+        if self.limit != Some(limit) {
+            self.limit = Some(limit);
+            self.tokens = capacity;
+            self.updated = now;
+        }
+        let rate = capacity / limit.per.as_secs_f64();
+        let refill = now.saturating_duration_since(self.updated).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(capacity) - 1.0;
+        self.updated = now;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+impl AllyApi {
+    /// Reserve a slot for a request under the applicable rate limit and
+    /// return how long to wait before sending it
+    pub(crate) fn reserve_request_slot(&self, token_endpoint: bool) -> Duration {
+        let mut shared = self.shared();
+        let (limit, bucket) = if token_endpoint {
+            (self.token_rate_limit, &mut shared.token_bucket)
+        } else {
+            (self.rate_limit, &mut shared.api_bucket)
+        };
+        match limit {
+            Some(limit) => bucket.reserve(limit, Instant::now()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Wait until a request may be sent under the applicable rate limit
+    pub(crate) async fn wait_for_rate_limit(&self, token_endpoint: bool) {
+        let delay = self.reserve_request_slot(token_endpoint);
+        if !delay.is_zero() {
+            debug!("Rate limit reached, delaying request by {:?}", delay);
+            runtime::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_a_burst_then_spaces_requests() {
+        let now = Instant::now();
+        let limit = RateLimit::per_second(2);
+        let mut bucket = TokenBucket::default();
+        assert_eq!(bucket.reserve(limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, now), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(limit, now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let now = Instant::now();
+        let limit = RateLimit::per_second(2);
+        let mut bucket = TokenBucket::default();
+        bucket.reserve(limit, now);
+        bucket.reserve(limit, now);
+        let later = now + Duration::from_millis(500);
+        assert_eq!(bucket.reserve(limit, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, later), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn starts_over_when_the_limit_changes() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::default();
+        bucket.reserve(RateLimit::per_second(1), now);
+        assert_eq!(
+            bucket.reserve(RateLimit::per_minute(1), now),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn zero_limits_do_not_delay() {
+        let mut bucket = TokenBucket::default();
+        for _ in 0..3 {
+            assert_eq!(
+                bucket.reserve(RateLimit::per_second(0), Instant::now()),
+                Duration::ZERO
+            );
+        }
+    }
+}
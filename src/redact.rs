@@ -0,0 +1,71 @@
+use serde_json::Value;
+use std::fmt;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// JSON keys whose values are never logged
+const SECRET_KEYS: [&str; 5] = [
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "authorization",
+    "api_secret",
+];
+
+/// A string that is hidden from `Debug` and `Display` output
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(crate) struct Secret(String);
+
+impl Secret {
+    pub(crate) fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// The secret value, for use in requests only
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Make a raw response body safe for logging
+///
+/// Values of credential fields such as `access_token` are masked at any
+/// depth. Bodies that are not JSON are not echoed at all.
+///
+/// ```
+/// use danfoss_ally_rs::redact_body;
+///
+/// let body = r#"{"access_token":"abc","expires_in":"3599"}"#;
+/// assert_eq!(redact_body(body), r#"{"access_token":"[REDACTED]","expires_in":"3599"}"#);
+/// ```
+pub fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => format!("<{} bytes, not JSON>", body.len()),
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if SECRET_KEYS.contains(&key.to_lowercase().as_str()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
@@ -0,0 +1,84 @@
+use crate::runtime::SystemTime;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Metadata of the most recent HTTP response, see [`AllyApi::last_response`](crate::AllyApi::last_response)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseMeta {
+    /// API path that was requested, e.g. `/ally/devices`
+    pub path: String,
+    /// HTTP status code
+    pub status: u16,
+    /// When the response arrived
+    pub received_at: SystemTime,
+    /// Request id assigned by the server (`x-request-id`), if any
+    pub request_id: Option<String>,
+    /// Request limit of the current window (`x-ratelimit-limit`), if any
+    pub rate_limit: Option<u64>,
+    /// Requests left in the current window (`x-ratelimit-remaining`), if any
+    pub rate_limit_remaining: Option<u64>,
+    /// Seconds until the window resets (`x-ratelimit-reset`), if any
+    pub rate_limit_reset: Option<u64>,
+    /// How long to wait before retrying (`retry-after`), if any
+    pub retry_after: Option<Duration>,
+}
+
+impl ResponseMeta {
+    pub(crate) fn new(path: &str, status: u16, headers: &HeaderMap) -> Self {
+        let text = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+        let number = |name: &str| text(name).and_then(|value| value.parse::<u64>().ok());
+        Self {
+            path: path.to_string(),
+            status,
+            received_at: SystemTime::now(),
+            request_id: text("x-request-id").or_else(|| text("x-correlation-id")),
+            rate_limit: number("x-ratelimit-limit"),
+            rate_limit_remaining: number("x-ratelimit-remaining"),
+            rate_limit_reset: number("x-ratelimit-reset"),
+            retry_after: text("retry-after").and_then(|value| parse_retry_after(&value)),
+        }
+    }
+}
+
+/// Parse a `Retry-After` value, given either in seconds or as an HTTP date.
+/// Dates in the past mean no delay. HTTP dates need the `chrono` feature.
+///
+/// ```
+/// use danfoss_ally_rs::parse_retry_after;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+/// # #[cfg(feature = "chrono")]
+/// assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+/// assert_eq!(parse_retry_after("soon"), None);
+/// ```
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    retry_after_date(value)
+}
+
+#[cfg(feature = "chrono")]
+fn retry_after_date(value: &str) -> Option<Duration> {
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(not(feature = "chrono"))]
+fn retry_after_date(_value: &str) -> Option<Duration> {
+    None
+}
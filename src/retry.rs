@@ -0,0 +1,125 @@
+use crate::{AllyApi, AllyError};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Randomization applied to backoff delays
+///
+/// Randomizing keeps many clients started with the same configuration from
+/// retrying in lockstep after an outage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Jitter {
+    /// Plain exponential backoff
+    None,
+    /// A random delay between zero and the exponential backoff
+    #[default]
+    Full,
+    /// A random delay between the base delay and three times the previous delay
+    Decorrelated,
+}
+
+/// Exponential backoff with configurable jitter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub base: Duration,
+    /// Upper bound for any delay
+    pub max: Duration,
+    /// Randomization applied to the delays
+    pub jitter: Jitter,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            jitter: Jitter::Full,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before retry number `attempt` (starting at 0). `previous` is the
+    /// delay used before the last retry and only matters for
+    /// [`Jitter::Decorrelated`].
+    ///
+    /// ```
+    /// use danfoss_ally_rs::{Backoff, Jitter};
+    /// use std::time::Duration;
+    ///
+    /// let backoff = Backoff {
+    ///     base: Duration::from_secs(1),
+    ///     max: Duration::from_secs(10),
+    ///     jitter: Jitter::None,
+    /// };
+    /// assert_eq!(backoff.delay(2, Duration::ZERO), Duration::from_secs(4));
+    /// assert_eq!(backoff.delay(8, Duration::ZERO), Duration::from_secs(10));
+    /// ```
+    pub fn delay(&self, attempt: u32, previous: Duration) -> Duration {
+        let exponential = self
+            .base
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max);
+        match self.jitter {
+            Jitter::None => exponential,
+            Jitter::Full => exponential.mul_f64(random_fraction()),
+            Jitter::Decorrelated => {
+                let upper = previous.max(self.base).saturating_mul(3).min(self.max);
+                let span = upper.saturating_sub(self.base);
+                (self.base + span.mul_f64(random_fraction())).min(self.max)
+            }
+        }
+    }
+}
+
+/// How often and how fast failed requests are retried, see [`AllyApi::retry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts per request including the first one. Default: 3
+    pub max_attempts: u32,
+    /// Delays between the attempts
+    pub backoff: Backoff,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+impl AllyApi {
+    /// Delay before retrying a request that failed with `error` on attempt
+    /// number `attempt` (starting at 0), or `None` if it is not retried.
+    /// A `Retry-After` delay requested by the API takes precedence over a
+    /// shorter backoff.
+    pub(crate) fn retry_delay(
+        &self,
+        attempt: u32,
+        previous: Duration,
+        error: &AllyError,
+    ) -> Option<Duration> {
+        let policy = self.retry?;
+        if !error.is_transient() || attempt + 1 >= policy.max_attempts {
+            return None;
+        }
+        let delay = policy.backoff.delay(attempt, previous);
+        Some(
+            error
+                .retry_after()
+                .map_or(delay, |retry_after| delay.max(retry_after)),
+        )
+    }
+}
+
+/// Random number in `[0, 1)`, seeded by the standard library's hasher keys
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,67 @@
+use crate::{AllyApi, TemperatureUnit};
+use serde::{Deserialize, Serialize};
+
+/// A room grouping thermostats and sensors
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Room {
+    /// Name of the room
+    pub name: String,
+    /// Device ids or aliases of the devices in the room
+    pub members: Vec<String>,
+}
+
+impl AllyApi {
+    /// Room of a device. The room in the device overrides wins, then the
+    /// membership in [`AllyApi::rooms`], then the `room` tag.
+    pub fn room_of(&self, device_id: &str) -> Option<&str> {
+        self.overrides_for(device_id)
+            .and_then(|overrides| overrides.room.as_deref())
+            .or_else(|| {
+                self.rooms
+                    .iter()
+                    .find(|room| {
+                        room.members
+                            .iter()
+                            .any(|member| self.aliases.get(member).unwrap_or(member) == device_id)
+                    })
+                    .map(|room| room.name.as_str())
+            })
+            .or_else(|| self.tag(device_id, "room"))
+    }
+
+    /// Names of all rooms that have at least one device, sorted
+    pub fn room_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .devices
+            .iter()
+            .filter_map(|device| self.room_of(&device.id))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Current temperature of a room: the average measured temperature of
+    /// its devices
+    pub fn room_temperature(&self, room: &str, unit: TemperatureUnit) -> Option<f32> {
+        average(
+            self.devices_in_room(room)
+                .into_iter()
+                .filter_map(|device| device.current_temperature(unit)),
+        )
+    }
+
+    /// Target temperature of a room: the average setpoint of its devices
+    pub fn room_setpoint(&self, room: &str, unit: TemperatureUnit) -> Option<f32> {
+        average(
+            self.devices_in_room(room)
+                .into_iter()
+                .filter_map(|device| device.setpoint(unit)),
+        )
+    }
+}
+
+fn average(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
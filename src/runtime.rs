@@ -0,0 +1,74 @@
+//! Clocks, timers and background tasks
+//!
+//! On native targets they come from std and tokio. On WebAssembly they come
+//! from the browser, where std clocks panic and tokio has no timers. Timers
+//! and tasks of the browser are not `Send`, so they run on the local task
+//! queue and report back through a channel.
+
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Wait for `duration`
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for `duration`
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    let (done, finished) = futures::channel::oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        gloo_timers::future::sleep(duration).await;
+        let _ = done.send(());
+    });
+    let _ = finished.await;
+}
+
+/// Run `future` for at most `duration`, `None` if it did not finish in time
+pub(crate) async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> Option<T> {
+    let future = std::pin::pin!(future);
+    let timer = std::pin::pin!(sleep(duration));
+    match futures::future::select(future, timer).await {
+        futures::future::Either::Left((output, _)) => Some(output),
+        futures::future::Either::Right(_) => None,
+    }
+}
+
+/// Run `task` in the background. Returns false without a tokio runtime to
+/// run it on.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn(task: impl Future<Output = ()> + Send + 'static) -> bool {
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(task);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Run `task` in the background
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn(task: impl Future<Output = ()> + Send + 'static) -> bool {
+    wasm_bindgen_futures::spawn_local(task);
+    true
+}
+
+/// Run blocking work such as file access in the background
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn_blocking(work: impl FnOnce() + Send + 'static) {
+    tokio::task::spawn_blocking(work);
+}
+
+/// Run blocking work such as file access. There are no threads to move it
+/// to, so it runs right away.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn_blocking(work: impl FnOnce() + Send + 'static) {
+    work();
+}
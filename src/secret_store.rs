@@ -0,0 +1,73 @@
+use crate::{AllyApi, ConfigError};
+
+/// Service name under which the credentials are kept in the platform secret
+/// store. The entries are named after the environment variables,
+/// `DANFOSS_API_KEY` and `DANFOSS_API_SECRET`.
+pub const KEYRING_SERVICE: &str = "danfoss-ally-rs";
+
+impl AllyApi {
+    /// Create new danfoss ally client with the credentials from the platform
+    /// secret store (Secret Service, Keychain or Credential Manager), see
+    /// [`KEYRING_SERVICE`]
+    pub fn from_keyring() -> Result<Self, ConfigError> {
+        let api_key = credential_from_keyring("DANFOSS_API_KEY")?;
+        let api_secret = credential_from_keyring("DANFOSS_API_SECRET")?;
+        Ok(Self::with_credentials(api_key, api_secret))
+    }
+
+    /// Save credentials in the platform secret store, so that
+    /// [`AllyApi::from_keyring`] and [`AllyApi::try_new`] find them
+    pub fn store_credentials_in_keyring(
+        api_key: &str,
+        api_secret: &str,
+    ) -> Result<(), ConfigError> {
+        let (api_key, api_secret) = (api_key.to_string(), api_secret.to_string());
+        outside_runtime(move || {
+            keyring::Entry::new(KEYRING_SERVICE, "DANFOSS_API_KEY")?.set_password(&api_key)?;
+            keyring::Entry::new(KEYRING_SERVICE, "DANFOSS_API_SECRET")?
+                .set_password(&api_secret)?;
+            Ok(())
+        })
+    }
+}
+
+/// Read a credential from the platform secret store
+pub(crate) fn credential_from_keyring(name: &str) -> Result<String, ConfigError> {
+    let name = name.to_string();
+    outside_runtime(
+        move || match keyring::Entry::new(KEYRING_SERVICE, &name)?.get_password() {
+            Ok(value) => Ok(value),
+            Err(keyring::Error::NoEntry) => Err(ConfigError::MissingVariable(name)),
+            Err(e) => Err(e.into()),
+        },
+    )
+}
+
+/// Run a secret store call on a thread of its own. The Secret Service client
+/// blocks on its own tokio runtime, which panics on a thread that already
+/// drives one, e.g. inside `#[tokio::main]`.
+fn outside_runtime<T: Send + 'static>(
+    call: impl FnOnce() -> Result<T, ConfigError> + Send + 'static,
+) -> Result<T, ConfigError> {
+    std::thread::spawn(call)
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+impl From<keyring::Error> for ConfigError {
+    fn from(error: keyring::Error) -> Self {
+        ConfigError::Keyring(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lookup_works_inside_a_runtime() {
+        // Without a secret store in the test environment the lookup fails,
+        // but it must return instead of panicking
+        let _ = credential_from_keyring("DANFOSS_ALLY_RS_TEST_MISSING");
+    }
+}
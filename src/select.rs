@@ -0,0 +1,117 @@
+use crate::{AllyApi, Device};
+
+impl AllyApi {
+    /// Devices whose name or alias matches a glob pattern, e.g. `Bedroom*`.
+    ///
+    /// `*` matches any number of characters and `?` exactly one. Matching
+    /// ignores case. Devices are returned in the order of [`AllyApi::devices`].
+    pub fn select_devices(&self, pattern: &str) -> Vec<&Device> {
+        let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+        self.devices
+            .iter()
+            .filter(|device| {
+                glob_match(&pattern, &device.name)
+                    || self
+                        .aliases
+                        .iter()
+                        .any(|(alias, id)| id == &device.id && glob_match(&pattern, alias))
+            })
+            .collect()
+    }
+
+    /// Devices assigned to the given room, see [`AllyApi::room_of`]
+    pub fn devices_in_room(&self, room: &str) -> Vec<&Device> {
+        self.devices
+            .iter()
+            .filter(|device| self.room_of(&device.id) == Some(room))
+            .collect()
+    }
+}
+
+fn glob_match(pattern: &[char], text: &str) -> bool {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+        glob_match(&pattern, text)
+    }
+
+    #[test]
+    fn literal_patterns_match_whole_names() {
+        assert!(matches("Kitchen", "kitchen"));
+        assert!(!matches("Kitchen", "Kitchen 2"));
+        assert!(!matches("Kitchen 2", "Kitchen"));
+        assert!(matches("", ""));
+        assert!(!matches("", "x"));
+    }
+
+    #[test]
+    fn star_matches_any_run_of_characters() {
+        assert!(matches("Bed*", "Bedroom"));
+        assert!(matches("Bed*", "Bed"));
+        assert!(matches("*room", "Living room"));
+        assert!(matches("*o*o*", "bathroom"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "axxbyybc"));
+        assert!(!matches("a*b*c", "axxbyyb"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        assert!(matches("Room ?", "Room 1"));
+        assert!(!matches("Room ?", "Room 12"));
+        assert!(!matches("Room ?", "Room "));
+        assert!(matches("Zimmer ??", "Zimmer Ü1"));
+    }
+
+    #[test]
+    fn devices_match_by_name_or_alias() {
+        let mut api = AllyApi::default();
+        for (id, name) in [("1", "Bedroom"), ("2", "Office"), ("3", "Guest bedroom")] {
+            api.devices.push(Device {
+                id: id.to_string(),
+                name: name.to_string(),
+                ..Device::default()
+            });
+        }
+        api.set_alias("bed-office", "2");
+
+        let ids = |pattern| -> Vec<&str> {
+            api.select_devices(pattern)
+                .iter()
+                .map(|device| device.id.as_str())
+                .collect()
+        };
+        assert_eq!(ids("*bed*"), ["1", "2", "3"]);
+        assert_eq!(ids("BEDROOM"), ["1"]);
+        assert!(ids("cellar").is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{AllyApi, DeviceKind};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Setpoints in degrees Celsius a device accepts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SetpointLimits {
+    /// Lowest setpoint
+    pub min: f32,
+    /// Highest setpoint
+    pub max: f32,
+    /// Resolution setpoints must be a multiple of
+    pub step: f32,
+}
+
+impl Default for SetpointLimits {
+    fn default() -> Self {
+        Self {
+            min: 5.0,
+            max: 35.0,
+            step: 0.5,
+        }
+    }
+}
+
+impl SetpointLimits {
+    /// Whether a setpoint lies within the limits and on a step
+    ///
+    /// ```
+    /// use danfoss_ally_rs::SetpointLimits;
+    ///
+    /// let limits = SetpointLimits::default();
+    /// assert!(limits.accepts(21.5));
+    /// assert!(!limits.accepts(21.3));
+    /// assert!(!limits.accepts(40.0));
+    /// ```
+    pub fn accepts(&self, celsius: f32) -> bool {
+        let on_step = self.step <= 0.0 || {
+            let steps = celsius / self.step;
+            (steps - steps.round()).abs() < 1e-3
+        };
+        (self.min..=self.max).contains(&celsius) && on_step
+    }
+
+    /// Round a setpoint to the nearest step, e.g. after converting it from
+    /// Fahrenheit
+    ///
+    /// ```
+    /// use danfoss_ally_rs::SetpointLimits;
+    ///
+    /// assert_eq!(SetpointLimits::default().round(21.1), 21.0);
+    /// assert_eq!(SetpointLimits::default().round(21.3), 21.5);
+    /// ```
+    pub fn round(&self, celsius: f32) -> f32 {
+        if self.step <= 0.0 {
+            return celsius;
+        }
+        (celsius / self.step).round() * self.step
+    }
+}
+
+/// Error returned before sending a setpoint the device would not accept
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidSetpoint {
+    /// Id of the device
+    pub device_id: String,
+    /// Requested setpoint in degrees Celsius
+    pub celsius: f32,
+    /// Limits of the device
+    pub limits: SetpointLimits,
+}
+
+impl fmt::Display for InvalidSetpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Setpoint {} °C for device {} must lie within {}-{} °C in steps of {} °C",
+            self.celsius, self.device_id, self.limits.min, self.limits.max, self.limits.step
+        )
+    }
+}
+
+impl Error for InvalidSetpoint {}
+
+impl AllyApi {
+    /// Setpoint limits of a device, by the kind of the cached device
+    pub fn setpoint_limits_for(&self, device_id: &str) -> SetpointLimits {
+        self.devices
+            .iter()
+            .find(|device| device.id == device_id)
+            .map(|device| device.kind())
+            .and_then(|kind: DeviceKind| self.setpoint_limits.get(&kind).copied())
+            .unwrap_or_default()
+    }
+
+    /// Check a setpoint against the limits of a device
+    pub fn validate_setpoint(&self, device_id: &str, celsius: f32) -> Result<(), InvalidSetpoint> {
+        let limits = self.setpoint_limits_for(device_id);
+        if limits.accepts(celsius) {
+            return Ok(());
+        }
+        Err(InvalidSetpoint {
+            device_id: device_id.to_string(),
+            celsius,
+            limits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Device, TemperatureUnit};
+
+    #[test]
+    fn limits_are_inclusive() {
+        let limits = SetpointLimits::default();
+        assert!(limits.accepts(5.0));
+        assert!(limits.accepts(35.0));
+        assert!(!limits.accepts(4.5));
+        assert!(!limits.accepts(35.5));
+        assert!(!limits.accepts(f32::NAN));
+    }
+
+    #[test]
+    fn setpoints_must_lie_on_a_step() {
+        let limits = SetpointLimits::default();
+        assert!(limits.accepts(20.5));
+        assert!(limits.accepts(0.1 * 205.0));
+        assert!(!limits.accepts(20.25));
+        assert!(!limits.accepts(20.1));
+
+        let fine = SetpointLimits {
+            step: 0.0,
+            ..limits
+        };
+        assert!(fine.accepts(20.1));
+    }
+
+    #[test]
+    fn rounding_snaps_to_the_nearest_step() {
+        let limits = SetpointLimits::default();
+        assert_eq!(limits.round(21.24), 21.0);
+        assert_eq!(limits.round(21.26), 21.5);
+        assert!(limits.accepts(limits.round(TemperatureUnit::Fahrenheit.celsius_of(71.0))));
+
+        let fine = SetpointLimits {
+            step: 0.0,
+            ..limits
+        };
+        assert_eq!(fine.round(21.26), 21.26);
+    }
+
+    #[test]
+    fn limits_follow_the_kind_of_the_cached_device() {
+        let mut api = AllyApi::default();
+        api.devices.push(Device {
+            id: "floor".to_string(),
+            device_type: "Danfoss Icon Room Thermostat".to_string(),
+            ..Device::default()
+        });
+        let icon = SetpointLimits {
+            min: 10.0,
+            max: 30.0,
+            step: 0.5,
+        };
+        api.setpoint_limits.insert(DeviceKind::IconController, icon);
+
+        assert_eq!(api.setpoint_limits_for("floor"), icon);
+        assert_eq!(api.setpoint_limits_for("other"), SetpointLimits::default());
+        assert!(api.validate_setpoint("other", 8.0).is_ok());
+        assert_eq!(
+            api.validate_setpoint("floor", 8.0),
+            Err(InvalidSetpoint {
+                device_id: "floor".to_string(),
+                celsius: 8.0,
+                limits: icon,
+            })
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::rate_limit::TokenBucket;
+use crate::redact::Secret;
+use crate::{AllyApi, Boost, CircuitBreaker, QuotaTracker, ResponseMeta};
+use std::collections::HashMap;
+use std::sync::{Arc, MutexGuard, PoisonError};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Request state of an [`AllyApi`] that its clones share
+///
+/// The lock is only held for short synchronous updates, never across an
+/// `.await`.
+#[derive(Debug, Default)]
+pub(crate) struct SharedState {
+    pub(crate) api_key: Secret,
+    pub(crate) api_secret: Secret,
+    pub(crate) last_correlation_id: Option<String>,
+    pub(crate) last_response: Option<ResponseMeta>,
+    pub(crate) quota: Option<QuotaTracker>,
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    pub(crate) api_bucket: TokenBucket,
+    pub(crate) token_bucket: TokenBucket,
+    pub(crate) boosts: Vec<Boost>,
+    pub(crate) frost_protected: HashMap<String, f32>,
+    pub(crate) setpoint_locks: HashMap<String, Arc<AsyncMutex<()>>>,
+}
+
+impl SharedState {
+    pub(crate) fn new(api_key: String, api_secret: String) -> Self {
+        Self {
+            api_key: Secret::new(api_key),
+            api_secret: Secret::new(api_secret),
+            ..Self::default()
+        }
+    }
+}
+
+impl AllyApi {
+    /// Lock the state shared with clones of this client
+    pub(crate) fn shared(&self) -> MutexGuard<'_, SharedState> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wait until no boost or frost protection of the device is being set up
+    /// or ended by this client or a clone. The setpoint of the device is
+    /// changed while the returned guard is held.
+    pub(crate) async fn lock_setpoint(&self, device_id: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .shared()
+            .setpoint_locks
+            .entry(device_id.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
@@ -0,0 +1,163 @@
+use crate::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Status codes whose readings are smoothed
+pub(crate) const SMOOTHED_CODES: [StatusCode; 3] = [
+    StatusCode::VaTemperature,
+    StatusCode::TempCurrent,
+    StatusCode::VaHumidity,
+];
+
+/// How noisy readings are smoothed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmoothingMethod {
+    /// Exponential moving average, `alpha` in `(0, 1]` weights the newest reading
+    Ema {
+        /// Weight of the newest reading
+        alpha: f32,
+    },
+    /// Plain average over the last `size` readings
+    Window {
+        /// Number of readings averaged
+        size: usize,
+    },
+}
+
+/// Smoothing of temperature and humidity readings
+///
+/// With smoothing enabled, changes of `va_temperature`, `temp_current` and
+/// `va_humidity` only produce [`DeviceEvent::StatusChanged`](crate::DeviceEvent::StatusChanged)
+/// entries once the smoothed value moved by at least `min_change` since it
+/// was last reported.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Smoothing {
+    /// Smoothing method
+    pub method: SmoothingMethod,
+    /// Smallest change of the smoothed value worth reporting, in wire units
+    /// (tenths of a degree or of a percent)
+    pub min_change: f32,
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            method: SmoothingMethod::Ema { alpha: 0.3 },
+            min_change: 5.0,
+        }
+    }
+}
+
+/// Smoothing state of a single reading
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Smoother {
+    window: VecDeque<f32>,
+    average: Option<f32>,
+    reported: Option<f32>,
+}
+
+impl Smoother {
+    /// Add a reading and return the new smoothed value
+    pub(crate) fn push(&mut self, method: SmoothingMethod, value: f32) -> f32 {
+        let average = match method {
+            SmoothingMethod::Ema { alpha } => match self.average {
+                Some(average) => average + alpha.clamp(0.0, 1.0) * (value - average),
+                None => value,
+            },
+            SmoothingMethod::Window { size } => {
+                self.window.push_back(value);
+                while self.window.len() > size.max(1) {
+                    self.window.pop_front();
+                }
+                self.window.iter().sum::<f32>() / self.window.len() as f32
+            }
+        };
+        self.average = Some(average);
+        self.reported.get_or_insert(average);
+        average
+    }
+
+    /// Current smoothed value
+    pub(crate) fn value(&self) -> Option<f32> {
+        self.average
+    }
+
+    /// Whether the smoothed value moved far enough to be reported. Marks it
+    /// as reported if so.
+    pub(crate) fn take_report(&mut self, min_change: f32) -> bool {
+        match (self.average, self.reported) {
+            (Some(average), Some(reported)) if (average - reported).abs() < min_change => false,
+            (average, _) => {
+                self.reported = average;
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ema_starts_at_the_first_reading() {
+        let method = SmoothingMethod::Ema { alpha: 0.5 };
+        let mut smoother = Smoother::default();
+        assert_eq!(smoother.value(), None);
+        assert_eq!(smoother.push(method, 200.0), 200.0);
+        assert_eq!(smoother.push(method, 210.0), 205.0);
+        assert_eq!(smoother.push(method, 205.0), 205.0);
+    }
+
+    #[test]
+    fn ema_alpha_is_clamped() {
+        let mut smoother = Smoother::default();
+        smoother.push(SmoothingMethod::Ema { alpha: 1.0 }, 200.0);
+        assert_eq!(
+            smoother.push(SmoothingMethod::Ema { alpha: 3.0 }, 220.0),
+            220.0
+        );
+        assert_eq!(
+            smoother.push(SmoothingMethod::Ema { alpha: -1.0 }, 100.0),
+            220.0
+        );
+    }
+
+    #[test]
+    fn window_averages_the_last_readings() {
+        let method = SmoothingMethod::Window { size: 3 };
+        let mut smoother = Smoother::default();
+        assert_eq!(smoother.push(method, 10.0), 10.0);
+        assert_eq!(smoother.push(method, 20.0), 15.0);
+        assert_eq!(smoother.push(method, 30.0), 20.0);
+        assert_eq!(smoother.push(method, 40.0), 30.0);
+    }
+
+    #[test]
+    fn empty_window_keeps_the_latest_reading() {
+        let method = SmoothingMethod::Window { size: 0 };
+        let mut smoother = Smoother::default();
+        smoother.push(method, 10.0);
+        assert_eq!(smoother.push(method, 20.0), 20.0);
+    }
+
+    #[test]
+    fn only_changes_of_min_change_are_reported() {
+        let method = SmoothingMethod::Window { size: 1 };
+        let mut smoother = Smoother::default();
+        // The first reading is the baseline
+        smoother.push(method, 200.0);
+        assert!(!smoother.take_report(5.0));
+
+        smoother.push(method, 204.0);
+        assert!(!smoother.take_report(5.0));
+        smoother.push(method, 196.0);
+        assert!(!smoother.take_report(5.0));
+        smoother.push(method, 205.0);
+        assert!(smoother.take_report(5.0));
+        // Measured from the value reported last
+        smoother.push(method, 208.0);
+        assert!(!smoother.take_report(5.0));
+    }
+}
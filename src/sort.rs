@@ -0,0 +1,61 @@
+use crate::{AllyApi, Device, TemperatureUnit};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Order in which [`AllyApi::devices`] are kept
+///
+/// Ties are broken by device id, so the order is stable between polls.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Device name, alphabetically
+    #[default]
+    Name,
+    /// Device id
+    Id,
+    /// Room as resolved by [`AllyApi::room_of`], unassigned devices last
+    Room,
+    /// Measured temperature, coldest first
+    Temperature,
+    /// Battery level, emptiest first
+    Battery,
+    /// Time when last seen online, most recent first
+    LastSeen,
+}
+
+impl AllyApi {
+    /// Change the sort order and re-sort the cached devices
+    pub fn sort_devices(&mut self, key: SortKey) {
+        self.sort_order = key;
+        let mut devices = std::mem::take(&mut self.devices);
+        self.sort(&mut devices, key);
+        self.devices = devices;
+    }
+
+    pub(crate) fn sort(&self, devices: &mut [Device], key: SortKey) {
+        devices.sort_by(|a, b| self.compare(key, a, b).then_with(|| a.id.cmp(&b.id)));
+    }
+
+    fn compare(&self, key: SortKey, a: &Device, b: &Device) -> Ordering {
+        match key {
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Id => Ordering::Equal,
+            SortKey::Room => missing_last(self.room_of(&a.id), self.room_of(&b.id)),
+            SortKey::Temperature => missing_last(
+                a.current_temperature(TemperatureUnit::Celsius),
+                b.current_temperature(TemperatureUnit::Celsius),
+            ),
+            SortKey::Battery => missing_last(a.battery(), b.battery()),
+            SortKey::LastSeen => b.active_time.cmp(&a.active_time),
+        }
+    }
+}
+
+fn missing_last<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
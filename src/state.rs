@@ -0,0 +1,89 @@
+use crate::runtime::SystemTime;
+use crate::{AllyApi, Boost, Device, DeviceAvailability, QuotaTracker, Token};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Runtime state of an [`AllyApi`] that can be persisted on shutdown and
+/// restored on start, so a restarted process resumes with a warm device
+/// cache and without fetching a new token.
+///
+/// The state contains the access token; store it where only the process
+/// itself can read it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientState {
+    /// Access token
+    pub token: Token,
+    /// Wall clock time when the token expires
+    pub token_expires_at: Option<SystemTime>,
+    /// Cached devices
+    pub devices: Vec<Device>,
+    /// Server time of the last devices response
+    pub server_time: Option<SystemTime>,
+    /// Measured clock skew in milliseconds
+    pub clock_skew_ms: Option<i64>,
+    /// Availability history per device id
+    pub availability: HashMap<String, DeviceAvailability>,
+    /// Request quota counters
+    pub quota: Option<QuotaTracker>,
+    /// Boosts whose previous setpoint still has to be restored
+    #[serde(default)]
+    pub boosts: Vec<Boost>,
+    /// Setpoints in degrees Celsius of frost protected devices, to restore later
+    #[serde(default)]
+    pub frost_protected: HashMap<String, f32>,
+}
+
+impl AllyApi {
+    /// Capture the runtime state for persistence
+    pub fn export_state(&self) -> ClientState {
+        let shared = self.shared();
+        ClientState {
+            token: self.tokens.token(),
+            token_expires_at: self.tokens.expires_at(),
+            devices: self.devices.clone(),
+            server_time: self.server_time,
+            clock_skew_ms: self.clock_skew_ms,
+            availability: self.availability.clone(),
+            quota: shared.quota.clone(),
+            boosts: shared.boosts.clone(),
+            frost_protected: shared.frost_protected.clone(),
+        }
+    }
+
+    /// Resume from a previously exported state.
+    ///
+    /// An already expired token is discarded, so the next request fetches a
+    /// fresh one. A quota tracker that is already configured keeps its file
+    /// and limits and only takes over counters of the current period.
+    /// Restored boosts are reverted when they end, like new ones.
+    pub fn restore_state(&mut self, state: ClientState) {
+        let remaining = state
+            .token_expires_at
+            .and_then(|expires_at| expires_at.duration_since(SystemTime::now()).ok());
+        match remaining {
+            Some(remaining) if !state.token.access_token.is_empty() => {
+                self.tokens.set(state.token, remaining);
+            }
+            _ => self.tokens.clear(),
+        }
+        self.devices = state.devices;
+        self.server_time = state.server_time;
+        self.clock_skew_ms = state.clock_skew_ms;
+        self.availability = state.availability;
+        {
+            let mut shared = self.shared();
+            shared.boosts = state.boosts.clone();
+            shared.frost_protected = state.frost_protected;
+            match (&mut shared.quota, state.quota) {
+                (Some(quota), Some(saved)) => quota.merge_counters(&saved),
+                (None, saved) => shared.quota = saved,
+                (Some(_), None) => {}
+            }
+        }
+        let now = SystemTime::now();
+        for boost in state.boosts {
+            let remaining = boost.until.duration_since(now).unwrap_or_default();
+            self.schedule_revert(boost, remaining);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Code of a device status value or command, e.g. `temp_set`
+///
+/// Codes unknown to this crate are kept verbatim in [`StatusCode::Other`],
+/// so new codes introduced by the API still round-trip. Codes compare and
+/// hash by their wire form, so an `Other` holding a known code equals the
+/// matching variant.
+///
+/// ```
+/// use danfoss_ally_rs::StatusCode;
+///
+/// assert_eq!(StatusCode::from("temp_set"), StatusCode::TempSet);
+/// assert_eq!(StatusCode::from("new_code").as_str(), "new_code");
+/// assert_eq!(StatusCode::Other("temp_set".into()), StatusCode::TempSet);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum StatusCode {
+    /// Target temperature in tenths of a degree Celsius (`temp_set`)
+    TempSet,
+    /// Measured temperature of thermostats in tenths of a degree Celsius (`temp_current`)
+    TempCurrent,
+    /// Measured temperature of room sensors in tenths of a degree Celsius (`va_temperature`)
+    VaTemperature,
+    /// Relative humidity in tenths of a percent (`va_humidity`)
+    VaHumidity,
+    /// Battery level in percent (`battery_percentage`)
+    BatteryPercentage,
+    /// Child lock (`child_lock`)
+    ChildLock,
+    /// Detected window state (`window_state`)
+    WindowState,
+    /// Open window detection enabled (`window_toggle`)
+    WindowToggle,
+    /// Operating mode (`mode`)
+    Mode,
+    /// Whether the device is heating (`work_state`)
+    WorkState,
+    /// Valve opening in percent (`valve_position`)
+    ValvePosition,
+    /// Fault bit field (`fault`)
+    Fault,
+    /// Warning banner (`banner_ctrl`)
+    BannerCtrl,
+    /// Firmware version (`sw_version`)
+    SwVersion,
+    /// Firmware upgrade state (`upgrade_status`)
+    UpgradeStatus,
+    /// Temperature calibration offset in tenths of a degree Celsius (`temp_offset`)
+    TempOffset,
+    /// Start of the holiday in seconds since the UNIX epoch (`holiday_start`)
+    HolidayStart,
+    /// End of the holiday in seconds since the UNIX epoch (`holiday_end`)
+    HolidayEnd,
+    /// Holiday temperature in tenths of a degree Celsius (`holiday_setting`)
+    HolidaySetting,
+    /// Radiator is covered (`radiator_covered`)
+    RadiatorCovered,
+    /// Load balancing enabled (`load_balance_enable`)
+    LoadBalanceEnable,
+    /// Mean load of the room (`load_room_mean`)
+    LoadRoomMean,
+    /// Load estimate of the radiator (`load_estimate`)
+    LoadEstimate,
+    /// Temperature of an external room sensor in tenths of a degree Celsius (`ext_measured_rs`)
+    ExtMeasuredRs,
+    /// Linked room sensor (`room_sensor`)
+    RoomSensor,
+    /// On/off state of switchable devices (`switch`)
+    Switch,
+    /// A code not known to this crate, as reported
+    Other(String),
+}
+
+impl Default for StatusCode {
+    fn default() -> Self {
+        StatusCode::Other(String::new())
+    }
+}
+
+impl StatusCode {
+    /// Code as it appears on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatusCode::TempSet => "temp_set",
+            StatusCode::TempCurrent => "temp_current",
+            StatusCode::VaTemperature => "va_temperature",
+            StatusCode::VaHumidity => "va_humidity",
+            StatusCode::BatteryPercentage => "battery_percentage",
+            StatusCode::ChildLock => "child_lock",
+            StatusCode::WindowState => "window_state",
+            StatusCode::WindowToggle => "window_toggle",
+            StatusCode::Mode => "mode",
+            StatusCode::WorkState => "work_state",
+            StatusCode::ValvePosition => "valve_position",
+            StatusCode::Fault => "fault",
+            StatusCode::BannerCtrl => "banner_ctrl",
+            StatusCode::SwVersion => "sw_version",
+            StatusCode::UpgradeStatus => "upgrade_status",
+            StatusCode::TempOffset => "temp_offset",
+            StatusCode::HolidayStart => "holiday_start",
+            StatusCode::HolidayEnd => "holiday_end",
+            StatusCode::HolidaySetting => "holiday_setting",
+            StatusCode::RadiatorCovered => "radiator_covered",
+            StatusCode::LoadBalanceEnable => "load_balance_enable",
+            StatusCode::LoadRoomMean => "load_room_mean",
+            StatusCode::LoadEstimate => "load_estimate",
+            StatusCode::ExtMeasuredRs => "ext_measured_rs",
+            StatusCode::RoomSensor => "room_sensor",
+            StatusCode::Switch => "switch",
+            StatusCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<&str> for StatusCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "temp_set" => StatusCode::TempSet,
+            "temp_current" => StatusCode::TempCurrent,
+            "va_temperature" => StatusCode::VaTemperature,
+            "va_humidity" => StatusCode::VaHumidity,
+            "battery_percentage" => StatusCode::BatteryPercentage,
+            "child_lock" => StatusCode::ChildLock,
+            "window_state" => StatusCode::WindowState,
+            "window_toggle" => StatusCode::WindowToggle,
+            "mode" => StatusCode::Mode,
+            "work_state" => StatusCode::WorkState,
+            "valve_position" => StatusCode::ValvePosition,
+            "fault" => StatusCode::Fault,
+            "banner_ctrl" => StatusCode::BannerCtrl,
+            "sw_version" => StatusCode::SwVersion,
+            "upgrade_status" => StatusCode::UpgradeStatus,
+            "temp_offset" => StatusCode::TempOffset,
+            "holiday_start" => StatusCode::HolidayStart,
+            "holiday_end" => StatusCode::HolidayEnd,
+            "holiday_setting" => StatusCode::HolidaySetting,
+            "radiator_covered" => StatusCode::RadiatorCovered,
+            "load_balance_enable" => StatusCode::LoadBalanceEnable,
+            "load_room_mean" => StatusCode::LoadRoomMean,
+            "load_estimate" => StatusCode::LoadEstimate,
+            "ext_measured_rs" => StatusCode::ExtMeasuredRs,
+            "room_sensor" => StatusCode::RoomSensor,
+            "switch" => StatusCode::Switch,
+            code => StatusCode::Other(code.to_string()),
+        }
+    }
+}
+
+impl From<String> for StatusCode {
+    fn from(code: String) -> Self {
+        StatusCode::from(code.as_str())
+    }
+}
+
+impl From<&StatusCode> for StatusCode {
+    fn from(code: &StatusCode) -> Self {
+        code.clone()
+    }
+}
+
+impl From<StatusCode> for String {
+    fn from(code: StatusCode) -> Self {
+        match code {
+            StatusCode::Other(code) => code,
+            code => code.as_str().to_string(),
+        }
+    }
+}
+
+impl PartialEq for StatusCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for StatusCode {}
+
+impl Hash for StatusCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for StatusCode {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for StatusCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    fn hash(code: &StatusCode) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn other_holding_a_known_code_equals_the_variant() {
+        let other = StatusCode::Other("child_lock".to_string());
+        assert_eq!(other, StatusCode::ChildLock);
+        assert_eq!(hash(&other), hash(&StatusCode::ChildLock));
+        assert_ne!(StatusCode::Other("lock".to_string()), StatusCode::ChildLock);
+
+        let codes: HashSet<StatusCode> = [other, StatusCode::ChildLock].into_iter().collect();
+        assert_eq!(codes.len(), 1);
+    }
+
+    #[test]
+    fn codes_round_trip_through_their_wire_form() {
+        for code in [
+            StatusCode::TempSet,
+            StatusCode::Switch,
+            StatusCode::from("x_new"),
+        ] {
+            let json = serde_json::to_string(&code).unwrap();
+            assert_eq!(json, format!("\"{}\"", code));
+            assert_eq!(serde_json::from_str::<StatusCode>(&json).unwrap(), code);
+        }
+        assert!(matches!(
+            serde_json::from_str::<StatusCode>("\"temp_set\"").unwrap(),
+            StatusCode::TempSet
+        ));
+    }
+
+    #[test]
+    fn codes_compare_with_strings() {
+        assert!(StatusCode::TempSet == "temp_set");
+        assert!(StatusCode::Other("x".to_string()) == *"x");
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{AllyApi, Device, WindowState};
+use serde::{Deserialize, Serialize};
+
+/// Battery level in percent below which a battery counts as low, unless
+/// overridden per device
+const DEFAULT_LOW_BATTERY_THRESHOLD: u8 = 20;
+
+/// House-level overview of all devices, see [`AllyApi::summary`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HouseSummary {
+    /// Number of devices
+    pub devices: usize,
+    /// Number of devices that are online
+    pub online: usize,
+    /// Number of devices that are offline
+    pub offline: usize,
+    /// Number of devices with a low battery
+    pub low_battery: usize,
+    /// Average measured temperature, in the client's temperature unit
+    pub average_temperature: Option<f32>,
+    /// Lowest measured temperature, in the client's temperature unit
+    pub min_temperature: Option<f32>,
+    /// Highest measured temperature, in the client's temperature unit
+    pub max_temperature: Option<f32>,
+    /// Number of devices that detect an open window
+    pub open_windows: usize,
+    /// Number of devices that are currently heating
+    pub heating: usize,
+}
+
+impl AllyApi {
+    /// Summarize the cached devices in one struct, e.g. for a dashboard
+    pub fn summary(&self) -> HouseSummary {
+        let temperatures: Vec<f32> = self
+            .devices
+            .iter()
+            .filter_map(|device| device.current_temperature(self.temperature_unit))
+            .collect();
+        let online = self.devices.iter().filter(|device| device.online).count();
+        HouseSummary {
+            devices: self.devices.len(),
+            online,
+            offline: self.devices.len() - online,
+            low_battery: self
+                .devices
+                .iter()
+                .filter(|device| self.battery_is_low(device))
+                .count(),
+            average_temperature: (!temperatures.is_empty())
+                .then(|| temperatures.iter().sum::<f32>() / temperatures.len() as f32),
+            min_temperature: temperatures.iter().copied().reduce(f32::min),
+            max_temperature: temperatures.iter().copied().reduce(f32::max),
+            open_windows: self.devices.iter().filter(|d| window_open(d)).count(),
+            heating: self.devices.iter().filter(|d| d.is_heating()).count(),
+        }
+    }
+
+    fn battery_is_low(&self, device: &Device) -> bool {
+        let threshold = self
+            .overrides_for(&device.id)
+            .and_then(|overrides| overrides.low_battery_threshold)
+            .unwrap_or(DEFAULT_LOW_BATTERY_THRESHOLD);
+        device.is_battery_low(threshold)
+    }
+}
+
+fn window_open(device: &Device) -> bool {
+    device.window_state() == Some(WindowState::Open)
+}
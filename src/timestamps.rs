@@ -0,0 +1,58 @@
+use crate::Device;
+use chrono::{DateTime, FixedOffset, Utc};
+
+/// Convert seconds since the UNIX epoch, `None` for zero or out of range values
+fn from_epoch_seconds(seconds: i64) -> Option<DateTime<Utc>> {
+    (seconds > 0)
+        .then(|| DateTime::from_timestamp(seconds, 0))
+        .flatten()
+}
+
+impl Device {
+    /// When the device was last seen online (`active_time`)
+    pub fn active_at(&self) -> Option<DateTime<Utc>> {
+        from_epoch_seconds(self.active_time)
+    }
+
+    /// When the device was set up (`create_time`)
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        from_epoch_seconds(self.create_time)
+    }
+
+    /// When the device last updated its settings (`update_time`)
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        from_epoch_seconds(self.update_time)
+    }
+
+    /// UTC offset of the device's `time_zone`, e.g. `+01:00`
+    ///
+    /// ```
+    /// use danfoss_ally_rs::Device;
+    ///
+    /// let device = Device { time_zone: "+01:00".to_string(), ..Default::default() };
+    /// assert_eq!(device.utc_offset().unwrap().local_minus_utc(), 3600);
+    /// ```
+    pub fn utc_offset(&self) -> Option<FixedOffset> {
+        let time_zone = self.time_zone.trim();
+        let time_zone = time_zone
+            .strip_prefix("UTC")
+            .or_else(|| time_zone.strip_prefix("GMT"))
+            .unwrap_or(time_zone);
+        let (sign, offset) = if let Some(offset) = time_zone.strip_prefix('+') {
+            (1, offset)
+        } else if let Some(offset) = time_zone.strip_prefix('-') {
+            (-1, offset)
+        } else {
+            return None;
+        };
+        let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+        let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+        FixedOffset::east_opt(sign * seconds)
+    }
+
+    /// Convert a point in time into the device's local time, if its
+    /// `time_zone` is a UTC offset
+    pub fn local_time(&self, time: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+        self.utc_offset().map(|offset| time.with_timezone(&offset))
+    }
+}
@@ -0,0 +1,145 @@
+use crate::runtime::{Instant, SystemTime};
+use crate::Token;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Expiry of the current token, captured when the token response arrived
+#[derive(Debug, Clone, Copy)]
+struct TokenDeadline {
+    /// Monotonic deadline, unaffected by wall clock adjustments
+    monotonic: Instant,
+    /// Wall clock deadline, which keeps running while the system is suspended
+    wall: SystemTime,
+}
+
+/// Token, expiry and request timing held by a [`TokenManager`]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TokenState {
+    token: Token,
+    deadline: Option<TokenDeadline>,
+    last_request: Option<Instant>,
+}
+
+/// Holds the access token of an [`AllyApi`](crate::AllyApi)
+///
+/// Reading the token only takes a short read lock, so the token can be
+/// inspected from other tasks through a clone of the manager while the
+/// client is busy. Clones share the same token. Token refreshes are
+/// serialized, so clients sharing a manager request a new token only once.
+#[derive(Debug, Clone, Default)]
+pub struct TokenManager {
+    state: Arc<RwLock<TokenState>>,
+    refresh: Arc<Mutex<()>>,
+}
+
+impl TokenManager {
+    /// Create a manager without a token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of the current token
+    pub fn token(&self) -> Token {
+        self.read().token.clone()
+    }
+
+    /// Whether no token is held
+    pub fn is_empty(&self) -> bool {
+        self.read().token.access_token.is_empty()
+    }
+
+    /// Wall clock time when the current token expires, `None` without a token
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.deadline().map(|deadline| deadline.wall)
+    }
+
+    /// Time left until the current token expires, `None` without a token.
+    ///
+    /// The earlier of the monotonic and the wall clock deadline counts; an
+    /// expired token has zero time left.
+    pub fn remaining(&self) -> Option<Duration> {
+        let deadline = self.deadline()?;
+        let monotonic = deadline.monotonic.saturating_duration_since(Instant::now());
+        let wall = deadline
+            .wall
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        Some(monotonic.min(wall))
+    }
+
+    /// Whether a token is held and has not expired yet
+    pub fn is_valid(&self) -> bool {
+        self.remaining()
+            .is_some_and(|remaining| !remaining.is_zero())
+    }
+
+    /// Whether the token is missing or expires within `margin`. Tokens valid
+    /// for less than twice the margin are renewed after half their lifetime.
+    pub fn needs_refresh(&self, margin: Duration) -> bool {
+        let state = self.read();
+        let margin = margin.min(state.token.expires_in() / 2);
+        match state.deadline {
+            Some(deadline) if !state.token.access_token.is_empty() => {
+                Instant::now() + margin >= deadline.monotonic
+                    || SystemTime::now() + margin >= deadline.wall
+            }
+            _ => true,
+        }
+    }
+
+    /// The access token to send with requests
+    pub(crate) fn access_token(&self) -> String {
+        self.read().token.access_token.clone()
+    }
+
+    /// Replace the token with one that expires after `remaining`
+    pub(crate) fn set(&self, token: Token, remaining: Duration) {
+        let mut state = self.write();
+        state.token = token;
+        state.deadline = Some(TokenDeadline {
+            monotonic: Instant::now() + remaining,
+            wall: SystemTime::now() + remaining,
+        });
+    }
+
+    /// Drop the token
+    pub(crate) fn clear(&self) {
+        let mut state = self.write();
+        state.token = Token::default();
+        state.deadline = None;
+    }
+
+    /// Note that a token request is being sent
+    pub(crate) fn mark_requested(&self) {
+        self.write().last_request = Some(Instant::now());
+    }
+
+    /// Time since the last token request, `None` if there was none
+    pub(crate) fn since_last_request(&self) -> Option<Duration> {
+        self.read()
+            .last_request
+            .map(|requested| requested.elapsed())
+    }
+
+    /// Wait until no other refresh is in progress. The token is refreshed
+    /// while the returned guard is held.
+    pub(crate) async fn lock_refresh(&self) -> OwnedMutexGuard<()> {
+        self.refresh.clone().lock_owned().await
+    }
+
+    fn deadline(&self) -> Option<TokenDeadline> {
+        let state = self.read();
+        state
+            .deadline
+            .filter(|_| !state.token.access_token.is_empty())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, TokenState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, TokenState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
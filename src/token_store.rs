@@ -0,0 +1,208 @@
+use crate::runtime::SystemTime;
+use crate::{AllyApi, Token};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Keeps the access token between process restarts, see [`AllyApi::token_store`]
+pub trait TokenStore: fmt::Debug + Send + Sync {
+    /// The stored token, or `None` if nothing was stored yet
+    fn load(&self) -> io::Result<Option<Token>>;
+    /// Replace the stored token
+    fn save(&self, token: &Token) -> io::Result<()>;
+}
+
+/// Stores the token as JSON in a file.
+///
+/// The file contains the access token; it is created readable by the
+/// owner only on Unix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    /// Store the token in `path`
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Store the token in `danfoss-ally-rs/token.json` in the user's cache
+    /// directory: `$XDG_CACHE_HOME`, or `~/.cache` if it is not set.
+    /// `None` if neither can be determined.
+    ///
+    /// Clients of different accounts can share the file; a token is only
+    /// reused by a client with the API key it was issued for.
+    pub fn in_cache_dir() -> Option<Self> {
+        let cache_dir = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+        Some(Self::new(
+            cache_dir.join("danfoss-ally-rs").join("token.json"),
+        ))
+    }
+
+    /// Path of the token file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> io::Result<Option<Token>> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, token: &Token) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        io::Write::write_all(
+            &mut options.open(&self.path)?,
+            serde_json::to_string(token)?.as_bytes(),
+        )
+    }
+}
+
+impl AllyApi {
+    /// Take over a still valid token from `token_store`, if one is set.
+    ///
+    /// Tokens that expire within `token_refresh_margin` are ignored, as are
+    /// tokens without `issued_at`, whose expiry is unknown, and tokens issued
+    /// for another API key.
+    pub(crate) fn load_stored_token(&self) {
+        let Some(store) = &self.token_store else {
+            return;
+        };
+        let token = match store.load() {
+            Ok(Some(token)) => token,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not load the stored token. {}", e);
+                return;
+            }
+        };
+        let fingerprint = key_fingerprint(self.shared().api_key.expose());
+        if token.account.as_deref() != Some(fingerprint.as_str()) {
+            debug!("The stored token belongs to other credentials");
+            return;
+        }
+        let remaining = token
+            .expires_at()
+            .and_then(|expires_at| expires_at.duration_since(SystemTime::now()).ok())
+            .filter(|remaining| *remaining > self.token_refresh_margin);
+        match remaining {
+            Some(remaining) if !token.access_token.is_empty() => {
+                debug!("Reusing the stored token, valid for {:?}", remaining);
+                self.tokens.set(token, remaining);
+            }
+            _ => debug!("The stored token has expired"),
+        }
+    }
+
+    /// Write the current token to `token_store`, if one is set
+    pub(crate) fn store_token(&self) {
+        if let Some(store) = &self.token_store {
+            if let Err(e) = store.save(&self.tokens.token()) {
+                warn!("Could not store the token. {}", e);
+            }
+        }
+    }
+}
+
+/// Stable fingerprint of an API key, to recognize its tokens without storing
+/// the key (64 bit FNV-1a)
+pub(crate) fn key_fingerprint(api_key: &str) -> String {
+    let hash = api_key
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<Option<Token>>);
+
+    impl TokenStore for MemoryStore {
+        fn load(&self) -> io::Result<Option<Token>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn save(&self, token: &Token) -> io::Result<()> {
+            *self.0.lock().unwrap() = Some(token.clone());
+            Ok(())
+        }
+    }
+
+    fn stored(account: Option<String>, issued_ago: Duration) -> Arc<MemoryStore> {
+        Arc::new(MemoryStore(Mutex::new(Some(Token {
+            access_token: "stored".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 3600,
+            issued_at: Some(SystemTime::now() - issued_ago),
+            account,
+        }))))
+    }
+
+    fn client(key: &str, store: Arc<MemoryStore>) -> AllyApi {
+        let mut api = AllyApi::with_credentials(key, "secret");
+        api.token_store = Some(store);
+        api.load_stored_token();
+        api
+    }
+
+    #[test]
+    fn fingerprint_is_stable_and_hides_the_key() {
+        assert_eq!(key_fingerprint(""), "cbf29ce484222325");
+        assert_eq!(key_fingerprint("key"), key_fingerprint("key"));
+        assert_ne!(key_fingerprint("key"), key_fingerprint("key2"));
+        assert!(!key_fingerprint("key").contains("key"));
+    }
+
+    #[test]
+    fn token_of_the_same_key_is_reused() {
+        let api = client("key", stored(Some(key_fingerprint("key")), Duration::ZERO));
+        assert_eq!(api.tokens.access_token(), "stored");
+    }
+
+    #[test]
+    fn token_of_another_key_is_ignored() {
+        let api = client(
+            "other",
+            stored(Some(key_fingerprint("key")), Duration::ZERO),
+        );
+        assert_eq!(api.tokens.access_token(), "");
+
+        let api = client("key", stored(None, Duration::ZERO));
+        assert_eq!(api.tokens.access_token(), "");
+    }
+
+    #[test]
+    fn token_about_to_expire_is_ignored() {
+        let api = client(
+            "key",
+            stored(Some(key_fingerprint("key")), Duration::from_secs(3590)),
+        );
+        assert_eq!(api.tokens.access_token(), "");
+    }
+}
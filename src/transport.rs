@@ -0,0 +1,95 @@
+use crate::AllyError;
+use futures::future::BoxFuture;
+use std::fmt::Debug;
+
+/// Sends the HTTP requests of an [`AllyApi`](crate::AllyApi)
+///
+/// The client builds complete requests, including the authorization and
+/// content type headers, and only needs them delivered. Implement this to
+/// use another HTTP library or to answer requests in tests without a
+/// network. [`ReqwestTransport`] is used by default.
+///
+/// ```
+/// use danfoss_ally_rs::{http, AllyError, HttpTransport};
+/// use futures::future::BoxFuture;
+///
+/// /// Answers every request with an empty device list
+/// #[derive(Debug)]
+/// struct NoDevices;
+///
+/// impl HttpTransport for NoDevices {
+///     fn send(
+///         &self,
+///         _request: http::Request<String>,
+///     ) -> BoxFuture<'_, Result<http::Response<String>, AllyError>> {
+///         Box::pin(async { Ok(http::Response::new(r#"{"result":[]}"#.to_string())) })
+///     }
+/// }
+/// ```
+pub trait HttpTransport: Debug + Send + Sync {
+    /// Send a request and read the whole response body. Unsuccessful HTTP
+    /// statuses are not errors; the client inspects the status itself.
+    fn send(
+        &self,
+        request: http::Request<String>,
+    ) -> BoxFuture<'_, Result<http::Response<String>, AllyError>>;
+}
+
+/// [`HttpTransport`] backed by a [`reqwest::Client`]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Send requests through `client`
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// The underlying client
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send(
+        &self,
+        request: http::Request<String>,
+    ) -> BoxFuture<'_, Result<http::Response<String>, AllyError>> {
+        Box::pin(execute(self.client.clone(), request))
+    }
+
+    /// Requests of the browser are not `Send`, so they run on the local task
+    /// queue and report back through a channel
+    #[cfg(target_arch = "wasm32")]
+    fn send(
+        &self,
+        request: http::Request<String>,
+    ) -> BoxFuture<'_, Result<http::Response<String>, AllyError>> {
+        let (done, response) = futures::channel::oneshot::channel();
+        let client = self.client.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let _ = done.send(execute(client, request).await);
+        });
+        Box::pin(async move {
+            response
+                .await
+                .unwrap_or_else(|_| Err(AllyError::Transport("request was dropped".into())))
+        })
+    }
+}
+
+async fn execute(
+    client: reqwest::Client,
+    request: http::Request<String>,
+) -> Result<http::Response<String>, AllyError> {
+    let res = client.execute(request.try_into()?).await?;
+    let mut response = http::Response::builder().status(res.status());
+    if let Some(headers) = response.headers_mut() {
+        *headers = res.headers().clone();
+    }
+    Ok(response.body(res.text().await?)?)
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Unit temperatures are exposed in
+///
+/// The API always uses tenths of a degree Celsius on the wire, e.g. `215`
+/// for 21.5 °C. Conversions from and to that format go through this type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    /// Degrees Celsius
+    #[default]
+    Celsius,
+    /// Degrees Fahrenheit
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// Convert a wire value in tenths of a degree Celsius into this unit
+    ///
+    /// ```
+    /// use danfoss_ally_rs::TemperatureUnit;
+    ///
+    /// assert_eq!(TemperatureUnit::Celsius.decode_deci_celsius(215.0), 21.5);
+    /// assert_eq!(TemperatureUnit::Fahrenheit.decode_deci_celsius(200.0), 68.0);
+    /// ```
+    pub fn decode_deci_celsius(self, raw: f32) -> f32 {
+        self.convert_celsius(raw / 10.0)
+    }
+
+    /// Convert a temperature in this unit into tenths of a degree Celsius,
+    /// rounded to the nearest integer as expected by the API
+    ///
+    /// ```
+    /// use danfoss_ally_rs::TemperatureUnit;
+    ///
+    /// assert_eq!(TemperatureUnit::Fahrenheit.encode_deci_celsius(68.0), 200);
+    /// ```
+    pub fn encode_deci_celsius(self, value: f32) -> i32 {
+        (self.celsius_of(value) * 10.0).round() as i32
+    }
+
+    /// Convert a temperature in degrees Celsius into this unit
+    pub fn convert_celsius(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// Convert a temperature in this unit into degrees Celsius
+    pub fn celsius_of(self, value: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    /// Unit symbol, e.g. `°C`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::runtime::{SystemTime, UNIX_EPOCH};
+use crate::{bool_value, AllyApi, AllyError, Command, Device, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Window state detected by a thermostat (`window_state`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowState {
+    /// An open window was detected and heating is paused
+    Open,
+    /// No open window detected
+    Closed,
+}
+
+/// Window detection state of a device as of the last poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowStatus {
+    /// Detected window state
+    pub state: WindowState,
+    /// Whether open window detection is enabled (`window_toggle`), if reported
+    pub detection_enabled: Option<bool>,
+    /// When the device last updated its status
+    pub updated_at: SystemTime,
+}
+
+impl Device {
+    /// Detected window state, if reported
+    pub fn window_state(&self) -> Option<WindowState> {
+        match self.status_value(StatusCode::WindowState)? {
+            Value::String(state) => match state.trim().to_ascii_lowercase().as_str() {
+                "open" => Some(WindowState::Open),
+                "close" | "closed" => Some(WindowState::Closed),
+                _ => None,
+            },
+            value => bool_value(value).map(|open| {
+                if open {
+                    WindowState::Open
+                } else {
+                    WindowState::Closed
+                }
+            }),
+        }
+    }
+
+    /// Window state together with the detection setting and the time of
+    /// the last status update, if the device reports a window state
+    pub fn window_status(&self) -> Option<WindowStatus> {
+        Some(WindowStatus {
+            state: self.window_state()?,
+            detection_enabled: self
+                .status_value(StatusCode::WindowToggle)
+                .and_then(bool_value),
+            updated_at: UNIX_EPOCH + Duration::from_secs(self.update_time.max(0) as u64),
+        })
+    }
+}
+
+impl AllyApi {
+    /// Enable or disable open window detection of a thermostat
+    pub async fn set_window_detection(
+        &self,
+        device_id: &str,
+        enabled: bool,
+    ) -> Result<(), AllyError> {
+        self.send_commands(
+            device_id,
+            vec![Command::new(StatusCode::WindowToggle, enabled)],
+        )
+        .await
+    }
+}
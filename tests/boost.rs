@@ -0,0 +1,95 @@
+mod common;
+
+use common::{client, response, token, MockTransport};
+use danfoss_ally_rs::protocol::{DEVICES_PATH, TOKEN_PATH};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Transport acting as a single thermostat "abc" whose setpoint, in tenths of
+/// a degree Celsius, is changed by commands
+fn thermostat(setpoint: Arc<Mutex<i64>>) -> Arc<MockTransport> {
+    MockTransport::new(move |request| {
+        let path = request.uri().path();
+        if path == TOKEN_PATH {
+            return token("t");
+        }
+        if path.ends_with("/commands") {
+            let body: serde_json::Value = serde_json::from_str(request.body()).unwrap();
+            *setpoint.lock().unwrap() = body["commands"][0]["value"].as_i64().unwrap();
+            return response(200, r#"{"result":true}"#);
+        }
+        let device = format!(
+            r#"{{"id":"abc","name":"Living room","status":[{{"code":"temp_set","value":{}}}]}}"#,
+            setpoint.lock().unwrap()
+        );
+        if path == DEVICES_PATH {
+            response(200, &format!(r#"{{"result":[{}],"t":0}}"#, device))
+        } else {
+            response(200, &format!(r#"{{"result":{},"t":0}}"#, device))
+        }
+    })
+}
+
+#[tokio::test]
+async fn boost_is_reverted_when_it_ends() {
+    let setpoint = Arc::new(Mutex::new(215));
+    let api = client(thermostat(setpoint.clone()));
+
+    api.boost("abc", 25.0, Duration::from_millis(50))
+        .await
+        .unwrap();
+    assert_eq!(*setpoint.lock().unwrap(), 250);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(*setpoint.lock().unwrap(), 215);
+    assert!(api.boosts().is_empty());
+}
+
+#[tokio::test]
+async fn cancelled_boost_restores_the_setpoint_once() {
+    let setpoint = Arc::new(Mutex::new(215));
+    let transport = thermostat(setpoint.clone());
+    let api = client(transport.clone());
+
+    api.boost("abc", 25.0, Duration::from_millis(100))
+        .await
+        .unwrap();
+    api.cancel_boost("abc").await.unwrap();
+    assert_eq!(*setpoint.lock().unwrap(), 215);
+    assert!(api.boost_of("abc").is_none());
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(transport.count("/ally/devices/abc/commands"), 2);
+}
+
+#[tokio::test]
+async fn second_boost_keeps_the_setpoint_before_the_first() {
+    let setpoint = Arc::new(Mutex::new(215));
+    let api = client(thermostat(setpoint.clone()));
+
+    api.boost("abc", 25.0, Duration::from_secs(3600))
+        .await
+        .unwrap();
+    api.boost("abc", 28.0, Duration::from_secs(3600))
+        .await
+        .unwrap();
+    assert_eq!(api.boost_of("abc").unwrap().previous, 21.5);
+
+    api.cancel_boost("abc").await.unwrap();
+    assert_eq!(*setpoint.lock().unwrap(), 215);
+}
+
+#[tokio::test]
+async fn boost_saves_the_setpoint_reported_now_instead_of_the_device_list() {
+    let setpoint = Arc::new(Mutex::new(215));
+    let mut api = client(thermostat(setpoint.clone()));
+    api.get_devices().await.unwrap();
+    *setpoint.lock().unwrap() = 190;
+
+    api.boost("abc", 25.0, Duration::from_secs(3600))
+        .await
+        .unwrap();
+    api.cancel_boost("abc").await.unwrap();
+
+    assert_eq!(*setpoint.lock().unwrap(), 190);
+}
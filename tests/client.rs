@@ -0,0 +1,281 @@
+mod common;
+
+use common::{client, device, response, token, MockTransport};
+use danfoss_ally_rs::protocol::TOKEN_PATH;
+use danfoss_ally_rs::{
+    AllyError, AllyFleet, Backoff, Command, Jitter, RetryPolicy, StatusCode, TemperatureUnit,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEVICE_PATH: &str = "/ally/devices/abc";
+
+#[tokio::test]
+async fn rejected_token_is_replaced_once_and_the_request_retried() {
+    let transport = MockTransport::sequence(vec![
+        token("first"),
+        response(401, r#"{"message":"token expired"}"#),
+        token("second"),
+        device("abc"),
+    ]);
+    let api = client(transport.clone());
+
+    let device = api.get_device("abc").await.unwrap();
+
+    assert_eq!(device.id, "abc");
+    assert_eq!(transport.count(TOKEN_PATH), 2);
+    assert_eq!(
+        transport.authorizations(DEVICE_PATH),
+        ["Bearer first", "Bearer second"]
+    );
+}
+
+#[tokio::test]
+async fn token_rejected_twice_is_unauthorized() {
+    let transport = MockTransport::sequence(vec![
+        token("first"),
+        response(401, "{}"),
+        token("second"),
+        response(401, "{}"),
+    ]);
+    let api = client(transport.clone());
+
+    let error = api.get_device("abc").await.unwrap_err();
+
+    assert!(matches!(error.inner(), AllyError::Unauthorized(_)));
+    assert_eq!(transport.count(TOKEN_PATH), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn clones_share_a_single_token_refresh() {
+    let token_requests = Arc::new(AtomicUsize::new(0));
+    let counter = token_requests.clone();
+    let transport = MockTransport::with_delay(Duration::from_millis(50), move |request| {
+        if request.uri().path() == TOKEN_PATH {
+            counter.fetch_add(1, Ordering::SeqCst);
+            token("shared")
+        } else {
+            device("abc")
+        }
+    });
+    let api = client(transport.clone());
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let api = api.clone();
+            tokio::spawn(async move { api.get_device("abc").await.map(|_| ()) })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+
+    assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(transport.count(DEVICE_PATH), 8);
+}
+
+#[tokio::test]
+async fn rejected_credentials_are_auth_errors() {
+    for status in [400, 401] {
+        let transport =
+            MockTransport::sequence(vec![response(status, r#"{"error":"invalid_client"}"#)]);
+        let api = client(transport);
+
+        let error = api.get_token().await.unwrap_err();
+
+        assert!(
+            matches!(error.inner(), AllyError::Auth(_)),
+            "{} gave {:?}",
+            status,
+            error
+        );
+        assert!(error.correlation_id().is_some());
+    }
+}
+
+#[tokio::test]
+async fn unsuccessful_statuses_map_to_errors() {
+    let cases = [
+        (403, "Forbidden"),
+        (404, "NotFound"),
+        (429, "RateLimited"),
+        (503, "ServerError"),
+        (418, "Api"),
+    ];
+    for (status, expected) in cases {
+        let transport = MockTransport::sequence(vec![token("t"), response(status, "{}")]);
+        let api = client(transport);
+
+        let error = api.get_device("abc").await.unwrap_err();
+
+        assert_eq!(kind(error.inner()), expected, "{} gave {:?}", status, error);
+        assert_eq!(error.status(), Some(status));
+    }
+}
+
+#[tokio::test]
+async fn retry_waits_for_retry_after() {
+    let transport = MockTransport::sequence(vec![
+        token("t"),
+        http_response_with_retry_after(503, "1"),
+        device("abc"),
+    ]);
+    let mut api = client(transport.clone());
+    api.retry = Some(RetryPolicy {
+        max_attempts: 2,
+        backoff: Backoff {
+            base: Duration::from_millis(1),
+            max: Duration::from_millis(1),
+            jitter: Jitter::None,
+        },
+    });
+
+    let started = Instant::now();
+    api.get_device("abc").await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(transport.count(DEVICE_PATH), 2);
+}
+
+#[tokio::test]
+async fn requests_are_not_retried_without_a_policy() {
+    let transport =
+        MockTransport::sequence(vec![token("t"), http_response_with_retry_after(503, "1")]);
+    let api = client(transport.clone());
+
+    let error = api.get_device("abc").await.unwrap_err();
+
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(1)));
+    assert_eq!(transport.count(DEVICE_PATH), 1);
+}
+
+#[tokio::test]
+async fn batch_replaces_a_token_rejected_midway() {
+    let tokens = AtomicUsize::new(0);
+    let transport = MockTransport::new(move |request| {
+        let authorization = request.headers().get("authorization");
+        if request.uri().path() == TOKEN_PATH {
+            match tokens.fetch_add(1, Ordering::SeqCst) {
+                0 => token("first"),
+                _ => token("second"),
+            }
+        } else if request.uri().path() == "/ally/devices/b/commands"
+            && authorization.is_some_and(|value| value == "Bearer first")
+        {
+            response(401, "{}")
+        } else {
+            response(200, r#"{"result":true}"#)
+        }
+    });
+    let mut api = client(transport.clone());
+    api.max_concurrent_requests = 1;
+    let batch = ["a", "b", "c"]
+        .into_iter()
+        .map(|id| {
+            (
+                id.to_string(),
+                vec![Command::new(StatusCode::ChildLock, true)],
+            )
+        })
+        .collect();
+
+    let results = api.send_commands_batch(batch).await.unwrap();
+
+    for (device_id, result) in results {
+        assert!(result.is_ok(), "{} gave {:?}", device_id, result);
+    }
+    assert_eq!(transport.count(TOKEN_PATH), 2);
+    assert_eq!(
+        transport.authorizations("/ally/devices/b/commands"),
+        ["Bearer first", "Bearer second"]
+    );
+    assert_eq!(
+        transport.authorizations("/ally/devices/c/commands"),
+        ["Bearer second"]
+    );
+}
+
+#[tokio::test]
+async fn fahrenheit_setpoints_are_sent_in_tenths_of_celsius() {
+    let transport = MockTransport::new(|request| {
+        if request.uri().path() == TOKEN_PATH {
+            token("t")
+        } else {
+            response(200, r#"{"result":true}"#)
+        }
+    });
+    let api = client(transport.clone());
+
+    api.set_temperature_in("abc", 70.0, TemperatureUnit::Fahrenheit)
+        .await
+        .unwrap();
+    api.set_temperature_in("abc", 21.5, TemperatureUnit::Celsius)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        transport.bodies("/ally/devices/abc/commands"),
+        [
+            r#"{"commands":[{"code":"temp_set","value":210}]}"#,
+            r#"{"commands":[{"code":"temp_set","value":215}]}"#
+        ]
+    );
+    let error = api
+        .set_temperature_in("abc", 100.0, TemperatureUnit::Fahrenheit)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, AllyError::InvalidSetpoint(_)),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn fleet_results_are_keyed_by_account() {
+    let mut fleet = AllyFleet::new();
+    fleet.add_account(
+        "home",
+        client(MockTransport::sequence(vec![
+            token("t"),
+            response(200, r#"{"result":[{"id":"abc"}],"t":0}"#),
+        ])),
+    );
+    fleet.add_account(
+        "office",
+        client(MockTransport::sequence(vec![
+            token("t"),
+            response(503, "{}"),
+        ])),
+    );
+
+    let results = fleet.get_devices().await;
+
+    assert_eq!(results.keys().collect::<Vec<_>>(), ["home", "office"]);
+    assert!(results["home"].is_ok());
+    assert!(results["office"].is_err());
+    assert_eq!(fleet.find_device("abc").unwrap().account, "home");
+}
+
+fn http_response_with_retry_after(
+    status: u16,
+    retry_after: &str,
+) -> danfoss_ally_rs::http::Response<String> {
+    let mut response = response(status, "{}");
+    response
+        .headers_mut()
+        .insert("retry-after", retry_after.parse().unwrap());
+    response
+}
+
+fn kind(error: &AllyError) -> &'static str {
+    match error {
+        AllyError::Forbidden(_) => "Forbidden",
+        AllyError::NotFound(_) => "NotFound",
+        AllyError::RateLimited { .. } => "RateLimited",
+        AllyError::ServerError(_) => "ServerError",
+        AllyError::Api(_) => "Api",
+        _ => "other",
+    }
+}
@@ -0,0 +1,156 @@
+//! In-memory transport answering requests without a network
+
+#![allow(dead_code)]
+
+use danfoss_ally_rs::{http, AllyApi, AllyError, HttpTransport};
+use futures::future::BoxFuture;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const BASE_URL: &str = "http://ally.test";
+
+type Handler = dyn Fn(&http::Request<String>) -> http::Response<String> + Send + Sync;
+
+/// Answers requests with a handler and records them
+pub struct MockTransport {
+    handler: Box<Handler>,
+    delay: Duration,
+    requests: Mutex<Vec<http::Request<String>>>,
+}
+
+impl std::fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockTransport").finish_non_exhaustive()
+    }
+}
+
+impl MockTransport {
+    pub fn new(
+        handler: impl Fn(&http::Request<String>) -> http::Response<String> + Send + Sync + 'static,
+    ) -> Arc<Self> {
+        Self::with_delay(Duration::ZERO, handler)
+    }
+
+    /// Answer every request only after `delay`, so concurrent requests overlap
+    pub fn with_delay(
+        delay: Duration,
+        handler: impl Fn(&http::Request<String>) -> http::Response<String> + Send + Sync + 'static,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            handler: Box::new(handler),
+            delay,
+            requests: Mutex::new(vec![]),
+        })
+    }
+
+    /// Answer the requests with `responses` in order
+    pub fn sequence(responses: Vec<http::Response<String>>) -> Arc<Self> {
+        let responses = Mutex::new(responses.into_iter());
+        Self::new(move |request| {
+            responses
+                .lock()
+                .unwrap()
+                .next()
+                .unwrap_or_else(|| panic!("unexpected request to {}", request.uri()))
+        })
+    }
+
+    /// Paths of the requests sent so far
+    pub fn paths(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.uri().path().to_string())
+            .collect()
+    }
+
+    /// Number of requests sent to `path`
+    pub fn count(&self, path: &str) -> usize {
+        self.paths().iter().filter(|sent| *sent == path).count()
+    }
+
+    /// Bodies of the requests sent to `path`
+    pub fn bodies(&self, path: &str) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.uri().path() == path)
+            .map(|request| request.body().clone())
+            .collect()
+    }
+
+    /// Authorization headers of the requests sent to `path`
+    pub fn authorizations(&self, path: &str) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.uri().path() == path)
+            .filter_map(|request| request.headers().get("authorization"))
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn send(
+        &self,
+        request: http::Request<String>,
+    ) -> BoxFuture<'_, Result<http::Response<String>, AllyError>> {
+        Box::pin(async move {
+            if !self.delay.is_zero() {
+                tokio::time::sleep(self.delay).await;
+            }
+            let response = (self.handler)(&request);
+            self.requests.lock().unwrap().push(request);
+            Ok(response)
+        })
+    }
+}
+
+/// Response with a status and a body
+pub fn response(status: u16, body: &str) -> http::Response<String> {
+    http::Response::builder()
+        .status(status)
+        .body(body.to_string())
+        .unwrap()
+}
+
+/// Successful token response handing out `access_token`
+pub fn token(access_token: &str) -> http::Response<String> {
+    response(
+        200,
+        &format!(
+            r#"{{"access_token":"{}","token_type":"Bearer","expires_in":"3599"}}"#,
+            access_token
+        ),
+    )
+}
+
+/// Successful response of the single device endpoint
+pub fn device(id: &str) -> http::Response<String> {
+    response(
+        200,
+        &format!(
+            r#"{{"result":{{"id":"{}","name":"Living room"}},"t":0}}"#,
+            id
+        ),
+    )
+}
+
+/// Client sending its requests through `transport`, without rate limits or
+/// spacing between token requests
+pub fn client(transport: Arc<MockTransport>) -> AllyApi {
+    let mut api = AllyApi::builder()
+        .credentials("key", "secret")
+        .base_url(BASE_URL)
+        .transport(transport)
+        .rate_limit(None)
+        .token_rate_limit(None)
+        .build()
+        .unwrap();
+    api.min_token_interval = Duration::ZERO;
+    api
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::{response, BASE_URL};
+use danfoss_ally_rs::protocol::{self, CommandsResponse, TOKEN_PATH};
+use danfoss_ally_rs::{http, AllyError, Command, DevicesResponse, StatusCode, Token};
+use std::time::Duration;
+
+#[test]
+fn token_request_uses_basic_auth() {
+    let request = protocol::token_request(BASE_URL, "key", "secret").unwrap();
+
+    assert_eq!(request.method(), http::Method::POST);
+    assert_eq!(request.uri().to_string(), "http://ally.test/oauth2/token");
+    assert_eq!(request.headers()["authorization"], "Basic a2V5OnNlY3JldA==");
+    assert_eq!(request.body(), "grant_type=client_credentials");
+}
+
+#[test]
+fn api_request_carries_the_token_and_body() {
+    let body = protocol::commands_body(&[Command::new(StatusCode::TempSet, 215)]);
+    let request = protocol::api_request(
+        BASE_URL,
+        http::Method::POST,
+        &protocol::device_commands_path("abc").unwrap(),
+        "token",
+        Some(&body),
+    )
+    .unwrap();
+
+    assert_eq!(
+        request.uri().to_string(),
+        "http://ally.test/ally/devices/abc/commands"
+    );
+    assert_eq!(request.headers()["authorization"], "Bearer token");
+    assert_eq!(request.headers()["content-type"], "application/json");
+    assert_eq!(
+        request.body(),
+        r#"{"commands":[{"code":"temp_set","value":215}]}"#
+    );
+}
+
+#[test]
+fn device_ids_stay_a_single_path_segment() {
+    assert_eq!(protocol::device_path("abc").unwrap(), "/ally/devices/abc");
+    assert_eq!(
+        protocol::device_status_path("a/../b#c").unwrap(),
+        "/ally/devices/a%2F..%2Fb%23c/status"
+    );
+    for id in ["", ".", ".."] {
+        assert!(protocol::device_commands_path(id).is_err(), "{:?}", id);
+    }
+}
+
+#[test]
+fn parse_response_reads_successful_bodies() {
+    let token: Token = protocol::parse_response(
+        TOKEN_PATH,
+        &response(
+            200,
+            r#"{"access_token":"abc","token_type":"Bearer","expires_in":3599}"#,
+        ),
+    )
+    .unwrap();
+    assert_eq!(token.expires_in(), Duration::from_secs(3599));
+
+    let devices: DevicesResponse = protocol::parse_response(
+        protocol::DEVICES_PATH,
+        &response(200, r#"{"result":[{"id":"a"},{"name":"no id"}],"t":1}"#),
+    )
+    .unwrap();
+    assert_eq!(devices.result.len(), 1);
+}
+
+#[test]
+fn parse_response_reports_unreadable_bodies() {
+    let error =
+        protocol::parse_response::<Token>(TOKEN_PATH, &response(200, "<html>")).unwrap_err();
+
+    match error {
+        AllyError::Deserialize { path, body, .. } => {
+            assert_eq!(path, TOKEN_PATH);
+            assert_eq!(body, "<html>");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn parse_response_maps_statuses() {
+    let path = protocol::DEVICES_PATH;
+    let parse = |status| protocol::parse_response::<DevicesResponse>(path, &response(status, "{}"));
+
+    assert!(matches!(parse(401), Err(AllyError::Unauthorized(_))));
+    assert!(matches!(parse(404), Err(AllyError::NotFound(_))));
+    assert!(matches!(parse(502), Err(AllyError::ServerError(_))));
+    assert!(matches!(
+        protocol::parse_response::<Token>(TOKEN_PATH, &response(400, "{}")),
+        Err(AllyError::Auth(_))
+    ));
+}
+
+#[test]
+fn parse_response_reads_retry_after() {
+    let mut throttled = response(429, "{}");
+    throttled
+        .headers_mut()
+        .insert("retry-after", "30".parse().unwrap());
+
+    let error = protocol::parse_response::<DevicesResponse>(protocol::DEVICES_PATH, &throttled)
+        .unwrap_err();
+
+    match error {
+        AllyError::RateLimited { retry_after, .. } => {
+            assert_eq!(retry_after, Some(Duration::from_secs(30)))
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn quota_violations_are_not_rate_limits() {
+    let body = r#"{"fault":{"detail":{"errorcode":"policies.ratelimit.QuotaViolation"}}}"#;
+
+    let error =
+        protocol::parse_response::<DevicesResponse>(protocol::DEVICES_PATH, &response(429, body))
+            .unwrap_err();
+
+    assert!(matches!(error, AllyError::Api(_)));
+    assert!(!error.is_transient());
+}
+
+#[test]
+fn parse_commands_response_checks_the_result() {
+    assert!(protocol::parse_commands_response("abc", &response(200, r#"{"result":true}"#)).is_ok());
+    assert!(matches!(
+        protocol::parse_commands_response("abc", &response(200, r#"{"result":false}"#)),
+        Err(AllyError::CommandRejected(rejected)) if rejected.device_id == "abc"
+    ));
+    match protocol::parse_commands_response("abc", &response(404, "{}")) {
+        Err(AllyError::NotFound(error)) => assert_eq!(error.path, "/ally/devices/abc/commands"),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(CommandsResponse { result: false }
+        .into_result("abc")
+        .is_err());
+}